@@ -3,23 +3,54 @@
 //!
 //! This module provides Prometheus Query API related methods.
 
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 
-use ::url::Url;
+use ::url::percent_encoding::{utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+use ::url::{ParseError, Url};
 use chrono::offset::Utc;
 use chrono::DateTime;
-use http::{uri, Uri};
+use futures::stream::{self, Stream};
+use futures_preview::future::{join_all, try_join_all};
+use futures_preview::io::AsyncReadExt;
+use futures_timer::Delay;
+use http::{header, uri, Method, StatusCode, Uri};
 use serde::Serialize;
 use surf::*;
 
+use crate::coalesce::InFlight;
+use crate::exposition::{parse_exposition, ScrapedMetric};
+use crate::format::{datetime_to_timestamp, duration_to_step, duration_to_timeout};
+use crate::functions::{sort_instants, SortOrder};
+use crate::query_log::{LoggedQuery, QueryLog};
 use crate::query_types::*;
-use crate::result_types::ApiResult;
+use crate::result_types::{
+    ApiOk, ApiResult, BackendKind, Instant, Metric, RawApiResult, ResponseMeta,
+};
+use crate::selector::{is_label_name, validate_selector, LabelSelector};
+use crate::transport::{
+    basic_auth_header, connection_error, is_overloaded, read_body, recv_maybe_json, recv_raw,
+    response_meta, status_error, utf8_body, with_deadline, DataArrayScanner, IsahcClient,
+    RequestHeaders, TlsConfig,
+};
+use crate::value_types::prometheus_types::{
+    PROQ_ADMIN_APIS_DISABLED, PROQ_METRIC_NAME_LABEL, PROQ_OPENMETRICS_CONTENT_TYPE,
+    PROQ_QUERY_ID_HEADER, PROQ_SCRAPE_ACCEPT,
+};
 
 use super::errors::*;
 
 const PROQ_INSTANT_QUERY_URL: &str = "/api/v1/query";
 const PROQ_RANGE_QUERY_URL: &str = "/api/v1/query_range";
+const PROQ_QUERY_EXEMPLARS_URL: &str = "/api/v1/query_exemplars";
 const PROQ_SERIES_URL: &str = "/api/v1/series";
 const PROQ_LABELS_URL: &str = "/api/v1/labels";
 const PROQ_TARGETS_URL: &str = "/api/v1/targets";
@@ -27,16 +58,37 @@ const PROQ_RULES_URL: &str = "/api/v1/rules";
 const PROQ_ALERTS_URL: &str = "/api/v1/alerts";
 const PROQ_ALERT_MANAGERS_URL: &str = "/api/v1/alertmanagers";
 const PROQ_STATUS_CONFIG_URL: &str = "/api/v1/status/config";
-const PROQ_STATUS_FLAGS_URL: &str = "/api/v1/status/config";
+const PROQ_STATUS_FLAGS_URL: &str = "/api/v1/status/flags";
+const PROQ_STATUS_TSDB_URL: &str = "/api/v1/status/tsdb";
+const PROQ_STATUS_BUILDINFO_URL: &str = "/api/v1/status/buildinfo";
+const PROQ_STATUS_WALREPLAY_URL: &str = "/api/v1/status/walreplay";
+const PROQ_STATUS_RUNTIMEINFO_URL: &str = "/api/v1/status/runtimeinfo";
+const PROQ_ADMIN_SNAPSHOT_URL: &str = "/api/v1/admin/tsdb/snapshot";
+const PROQ_ADMIN_DELETE_SERIES_URL: &str = "/api/v1/admin/tsdb/delete_series";
+const PROQ_ADMIN_CLEAN_TOMBSTONES_URL: &str = "/api/v1/admin/tsdb/clean_tombstones";
+const PROQ_METADATA_URL: &str = "/api/v1/metadata";
+const PROQ_TARGETS_METADATA_URL: &str = "/api/v1/targets/metadata";
+const PROQ_FEDERATE_URL: &str = "/federate";
+
+/// Longest wait for an alert manager probe, also without query timeout.
+const PROQ_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+type UtcTime = DateTime<Utc>;
+
 macro_rules! PROQ_LABEL_VALUES_URL {
     () => {
         "/api/v1/label/{}/values"
     };
 }
 
+enum SeriesStreamState {
+    Start(ProqResult<PreparedRequest>),
+    Streaming(surf::Response, DataArrayScanner, Option<Duration>),
+    Done,
+}
+
 ///
 /// Protocol type for the client
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ProqProtocol {
     /// HTTP transport
     HTTP,
@@ -44,120 +96,2864 @@ pub enum ProqProtocol {
     HTTPS,
 }
 
+impl ProqProtocol {
+    pub(crate) fn scheme(&self) -> &'static str {
+        match self {
+            ProqProtocol::HTTP => "http",
+            ProqProtocol::HTTPS => "https",
+        }
+    }
+}
+
+///
+/// Retry policy of transient request failures, see [ProqClient::with_retry_policy].
+///
+/// Backoff between attempts grows exponentially, starting from `initial_backoff`.
+/// Servers reporting to be overloaded, see [ProqError::ServerOverloaded], get more time
+/// to recover with a longer backoff.
+/// Every delay is randomized between half and the full backoff to spread retries of
+/// concurrent clients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// Backoff before the first retry
+    pub initial_backoff: Duration,
+    /// Factor the backoff grows with after every retry
+    pub backoff_multiplier: f64,
+    /// Factor the backoff is scaled with when the server is overloaded
+    pub overloaded_backoff_multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            backoff_multiplier: 2.0,
+            overloaded_backoff_multiplier: 5.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    ///
+    /// Backoff before the given retry, starting from zero, without jitter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let policy = RetryPolicy::default();
+    /// assert_eq!(policy.backoff(2), Duration::from_millis(400));
+    /// ```
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .mul_f64(self.backoff_multiplier.max(1.0).powi(retry as i32))
+    }
+
+    ///
+    /// Backoff before the given retry when the server is overloaded, without jitter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let policy = RetryPolicy::default();
+    /// assert_eq!(policy.overloaded_backoff(2), Duration::from_secs(2));
+    /// ```
+    pub fn overloaded_backoff(&self, retry: u32) -> Duration {
+        self.backoff(retry)
+            .mul_f64(self.overloaded_backoff_multiplier.max(1.0))
+    }
+
+    fn jittered_backoff(&self, retry: u32, overloaded: bool) -> Duration {
+        let random = RandomState::new().build_hasher().finish();
+        let jitter = 0.5 + (random % 1000) as f64 / 2000.0;
+        let backoff = if overloaded {
+            self.overloaded_backoff(retry)
+        } else {
+            self.backoff(retry)
+        };
+        backoff.mul_f64(jitter)
+    }
+}
+
 ///
 /// Main client structure.
+///
+/// Client is `Send` and `Sync`, all futures returned by its methods are `Send`.
+/// Queries can be spawned on multi-threaded executors.
+///
+/// Cloning is cheap, clones share the connection pool and the requests in flight.
+/// Options changed on a clone don't affect the other clones.
+#[derive(Clone)]
 pub struct ProqClient {
-    host: Url,
+    host: String,
+    protocol: ProqProtocol,
+    query_timeout: Option<Duration>,
+    match_encoding: ProqMatchEncoding,
+    /// Slot receiving the request instead of sending it, only set by [ProqClient::prepare].
+    prepared: Option<Arc<Mutex<Option<PreparedRequest>>>>,
+    max_response_bytes: Option<usize>,
+    default_range: Option<Duration>,
+    accept_encoding: Option<String>,
+    range_exemplars: bool,
+    /// Encoded `Authorization` header value, kept out of prepared requests.
+    authorization: Option<String>,
+    headers: HashMap<String, String>,
+    /// Normalized path prefix like `/prometheus`, empty when served from the root.
+    route_prefix: String,
+    /// Instant queries in flight, only tracked when coalescing is enabled.
+    in_flight: Option<Arc<InFlight>>,
+    retry_policy: Option<RetryPolicy>,
+    lossy_utf8: bool,
+    strict_warnings: bool,
+    query_log: Option<QueryLog>,
+    http_client: IsahcClient,
+}
+
+/// Credentials are redacted and only the names of extra headers are shown,
+/// so the client can be logged without leaking secrets.
+impl fmt::Debug for ProqClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut header_names: Vec<&String> = self.headers.keys().collect();
+        header_names.sort();
+        f.debug_struct("ProqClient")
+            .field("host", &self.host)
+            .field("protocol", &self.protocol)
+            .field("route_prefix", &self.route_prefix)
+            .field("query_timeout", &self.query_timeout)
+            .field(
+                "authorization",
+                &self.authorization.as_ref().map(|_| "<redacted>"),
+            )
+            .field("headers", &header_names)
+            .finish_non_exhaustive()
+    }
+}
+
+///
+/// Builder of [ProqClient].
+///
+/// Protocol defaults to HTTPS, host is required.
+pub struct ProqClientBuilder {
+    host: Option<String>,
     protocol: ProqProtocol,
     query_timeout: Option<Duration>,
+    authorization: Option<String>,
+    headers: HashMap<String, String>,
+    tls: TlsConfig,
+}
+
+impl Default for ProqClientBuilder {
+    fn default() -> Self {
+        ProqClientBuilder {
+            host: None,
+            protocol: ProqProtocol::HTTPS,
+            query_timeout: None,
+            authorization: None,
+            headers: HashMap::new(),
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+impl ProqClientBuilder {
+    ///
+    /// Get a builder with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Set host port combination string: e.g. `localhost:9090`.
+    ///
+    /// A path after the host, like in `localhost:9090/prometheus/`, is taken as route prefix,
+    /// see [ProqClient::with_route_prefix]. Trailing slashes are ignored.
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_owned());
+        self
+    }
+
+    ///
+    /// Set the [ProqProtocol] used to reach the host.
+    pub fn protocol(mut self, protocol: ProqProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    ///
+    /// Set the maximum query timeout, see [ProqClient::set_query_timeout].
+    pub fn query_timeout(mut self, query_timeout: Option<Duration>) -> Self {
+        self.query_timeout = query_timeout;
+        self
+    }
+
+    ///
+    /// Authenticate with HTTP Basic authentication, see [ProqClient::with_basic_auth].
+    pub fn basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.authorization = Some(basic_auth_header(username, password));
+        self
+    }
+
+    ///
+    /// Authenticate with a bearer token, see [ProqClient::with_bearer_token].
+    pub fn bearer_token(mut self, token: &str) -> Self {
+        self.authorization = Some(format!("Bearer {}", token));
+        self
+    }
+
+    ///
+    /// Send an extra header with every request, see [ProqClient::with_header].
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .insert(name.to_ascii_lowercase(), value.to_owned());
+        self
+    }
+
+    ///
+    /// Accept any server certificate over HTTPS, including self-signed, expired and
+    /// mismatching ones.
+    ///
+    /// **This is unsafe, never enable it in production.** Anybody in the network path can
+    /// impersonate the server then, read the queries and credentials, and forge results.
+    /// Only meant for development and test clusters with self-signed certificates.
+    pub fn insecure_skip_tls_verify(mut self, skip: bool) -> Self {
+        self.tls.insecure_skip_verify = skip;
+        self
+    }
+
+    ///
+    /// Trust the CA certificates of a PEM bundle file to verify the server certificate over HTTPS.
+    ///
+    /// Meant for servers with certificates signed by a private CA, verification stays enabled.
+    /// The bundle is read when the client is built, unreadable bundles and bundles without
+    /// valid PEM certificates fail the build with [ProqError::InvalidCertificate].
+    pub fn ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.tls.ca_bundle = Some(path.into());
+        self
+    }
+
+    ///
+    /// Authenticate with a client certificate over HTTPS, for servers requiring mutual TLS.
+    ///
+    /// Certificate and its unencrypted private key are PEM files, they are presented during
+    /// the TLS handshake. Both are read when the client is built, unreadable files, files
+    /// without valid PEM blocks and encrypted keys fail the build with
+    /// [ProqError::InvalidCertificate].
+    pub fn client_certificate(
+        mut self,
+        certificate: impl Into<PathBuf>,
+        private_key: impl Into<PathBuf>,
+    ) -> Self {
+        self.tls.client_certificate = Some((certificate.into(), private_key.into()));
+        self
+    }
+
+    ///
+    /// Build the client.
+    ///
+    /// Host is either a bare `host[:port]` or a full URL, it is validated here instead of
+    /// failing the first query. Missing host is reported as [ProqError::InvalidParameter],
+    /// unusable host or port, like `localhost:port`, as [ProqError::UrlParseError].
+    /// Credentials, query strings and fragments in the host are rejected with
+    /// [ProqError::InvalidParameter]. A scheme given with the host, like in
+    /// `http://localhost:9090`, must match the protocol, otherwise [ProqError::ProtocolMismatch]
+    /// is returned.
+    pub fn build(mut self) -> ProqResult<ProqClient> {
+        let host = self
+            .host
+            .take()
+            .ok_or_else(|| ProqError::InvalidParameter("missing host".into()))?;
+
+        let host = match host.split_once("://") {
+            Some((scheme, rest)) => {
+                if !scheme.eq_ignore_ascii_case(self.protocol.scheme()) {
+                    return Err(ProqError::ProtocolMismatch {
+                        scheme: scheme.to_owned(),
+                        protocol: self.protocol.scheme().to_owned(),
+                    });
+                }
+                rest
+            }
+            None => host.as_str(),
+        };
+        let url = Url::parse(&format!("{}://{}", self.protocol.scheme(), host))?;
+        let authority = match (url.host_str(), url.port()) {
+            (None, _) | (Some(""), _) => return Err(ParseError::EmptyHost.into()),
+            (Some(name), Some(port)) => format!("{}:{}", name, port),
+            (Some(name), None) => name.to_owned(),
+        };
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(ProqError::InvalidParameter(
+                "host must not contain credentials, use basic auth instead".into(),
+            ));
+        }
+        if url.query().is_some() || url.fragment().is_some() {
+            return Err(ProqError::InvalidParameter(format!(
+                "host `{}` must not contain a query or fragment",
+                host
+            )));
+        }
+
+        // A path given with the host, even if it is just a trailing slash, is the route prefix.
+        let mut client = self.finish(authority)?;
+        client.route_prefix = normalize_route_prefix(url.path());
+        Ok(client)
+    }
+
+    fn finish(self, host: String) -> ProqResult<ProqClient> {
+        Ok(ProqClient {
+            host,
+            protocol: self.protocol,
+            query_timeout: self.query_timeout,
+            match_encoding: ProqMatchEncoding::default(),
+            prepared: None,
+            max_response_bytes: None,
+            default_range: None,
+            accept_encoding: None,
+            range_exemplars: false,
+            authorization: self.authorization,
+            headers: self.headers,
+            route_prefix: String::new(),
+            in_flight: None,
+            retry_policy: None,
+            lossy_utf8: false,
+            strict_warnings: false,
+            query_log: None,
+            http_client: IsahcClient::new(&self.tls)?,
+        })
+    }
 }
 
 impl ProqClient {
     ///
-    /// Get a HTTPS using Proq client.
+    /// Get a HTTPS using Proq client.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - host port combination string: e.g. `localhost:9090`
+    /// * `query_timeout` - Maximum query timeout for the client, also the deadline of every request
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap();
+    ///# }
+    /// ```
+    pub fn new(host: &str, query_timeout: Option<Duration>) -> ProqResult<Self> {
+        Self::new_with_proto(host, ProqProtocol::HTTPS, query_timeout)
+    }
+
+    ///
+    /// Get a Proq client with specified protocol.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - host port combination string: e.g. `localhost:9090`
+    /// * `protocol` - [ProqProtocol] Currently either HTTP or HTTPS
+    /// * `query_timeout` - Maximum query timeout for the client, also the deadline of every request
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new_with_proto(
+    ///     "localhost:9090",
+    ///     ProqProtocol::HTTP,
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap();
+    ///# }
+    /// ```
+    pub fn new_with_proto(
+        host: &str,
+        protocol: ProqProtocol,
+        query_timeout: Option<Duration>,
+    ) -> ProqResult<Self> {
+        ProqClientBuilder::new()
+            .host(host)
+            .protocol(protocol)
+            .query_timeout(query_timeout)
+            .build()
+    }
+
+    ///
+    /// Get a [ProqClientBuilder] to configure a client step by step.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::builder()
+    ///     .host("localhost:9090")
+    ///     .protocol(ProqProtocol::HTTP)
+    ///     .query_timeout(Some(Duration::from_secs(5)))
+    ///     .bearer_token("eyJhbGciOiJIUzI1NiJ9")
+    ///     .header("X-Scope-OrgID", "tenant-1")
+    ///     .build()
+    ///     .unwrap();
+    ///# }
+    /// ```
+    pub fn builder() -> ProqClientBuilder {
+        ProqClientBuilder::new()
+    }
+
+    ///
+    /// Set how multiple series selectors are encoded into `match[]` parameters.
+    ///
+    /// Default is [ProqMatchEncoding::Repeated] which is what Prometheus expects.
+    /// Some legacy proxies expect a single comma joined value instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `match_encoding` - [ProqMatchEncoding] : Encoding style of the selectors
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_match_encoding(ProqMatchEncoding::CommaJoined);
+    ///# }
+    /// ```
+    pub fn with_match_encoding(mut self, match_encoding: ProqMatchEncoding) -> Self {
+        self.match_encoding = match_encoding;
+        self
+    }
+
+    ///
+    /// Build and validate the request of a call without any network I/O.
+    ///
+    /// The call runs on a clone of the client which stops at sending, the would-be request
+    /// is returned instead. Invalid parameters fail the same way as when sending.
+    /// Calls making several requests return the first one, calls finishing without making
+    /// any request return `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `call` - call to prepare, gets the client to call
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap();
+    ///
+    /// futures::executor::block_on(async {
+    ///     let request = client
+    ///         .prepare(|client| async move { client.instant_query("up", None).await })
+    ///         .await
+    ///         .unwrap()
+    ///         .unwrap();
+    ///     assert_eq!(request.url.path(), "/api/v1/query");
+    /// });
+    ///# }
+    /// ```
+    pub async fn prepare<T, F>(
+        &self,
+        call: impl FnOnce(ProqClient) -> F,
+    ) -> ProqResult<Option<PreparedRequest>>
+    where
+        F: Future<Output = ProqResult<T>>,
+    {
+        let prepared = Arc::new(Mutex::new(None));
+        let mut client = self.clone();
+        client.prepared = Some(prepared.clone());
+        client.in_flight = None;
+        client.query_log = None;
+
+        // The call fails at sending, the prepared request is its actual outcome then.
+        let result = call(client).await;
+        let request = prepared
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        match (request, result) {
+            (Some(request), _) => Ok(Some(request)),
+            (None, Err(e)) => Err(e),
+            (None, Ok(_)) => Ok(None),
+        }
+    }
+
+    ///
+    /// Limit the size of response bodies.
+    ///
+    /// Reading is aborted with [ProqError::ResponseTooLarge] when a response body exceeds the limit.
+    /// By default there is no limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_response_bytes` - Maximum response body size in bytes
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_max_response_bytes(Some(16 * 1024 * 1024));
+    ///# }
+    /// ```
+    pub fn with_max_response_bytes(mut self, max_response_bytes: Option<usize>) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    ///
+    /// Set a default time window for range queries.
+    ///
+    /// Range queries called without start and end are evaluated over `[now - start_rel, now]`.
+    /// If only end is given, start defaults to `end - start_rel`.
+    /// If only start is given, end defaults to now.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_rel` - Length of the default window ending at now
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_default_range(Duration::from_secs(15 * 60));
+    ///# }
+    /// ```
+    pub fn with_default_range(mut self, start_rel: Duration) -> Self {
+        self.default_range = Some(start_rel);
+        self
+    }
+
+    ///
+    /// Request the given `Accept-Encoding` for responses, e.g. `gzip`.
+    ///
+    /// Without it the HTTP client advertises all encodings it supports.
+    /// Compressed responses are decoded transparently, see [ProqClient::instant_query_with_meta]
+    /// for the encoded and decoded sizes.
+    ///
+    /// # Arguments
+    ///
+    /// * `accept_encoding` - Requested content encoding
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_accept_encoding(Some("gzip"));
+    ///# }
+    /// ```
+    pub fn with_accept_encoding(mut self, accept_encoding: Option<&str>) -> Self {
+        self.accept_encoding = accept_encoding.map(ToOwned::to_owned);
+        self
+    }
+
+    ///
+    /// Send `exemplar=true` with range queries.
+    ///
+    /// Vendor extension understood by some gateways which then return exemplars along with
+    /// the range query result. Plain Prometheus serves exemplars only through a separate endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `range_exemplars` - Whether range queries request exemplars
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_range_exemplars(true);
+    ///# }
+    /// ```
+    pub fn with_range_exemplars(mut self, range_exemplars: bool) -> Self {
+        self.range_exemplars = range_exemplars;
+        self
+    }
+
+    ///
+    /// Authenticate every request with HTTP Basic authentication, e.g. for Prometheus behind
+    /// a reverse proxy.
+    ///
+    /// Credentials are only attached when the request is sent, they are never part of
+    /// the requests returned by [ProqClient::prepare].
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - User name
+    /// * `password` - Password of the user
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_basic_auth("prometheus", "secret");
+    ///# }
+    /// ```
+    pub fn with_basic_auth(mut self, username: &str, password: &str) -> Self {
+        self.authorization = Some(basic_auth_header(username, password));
+        self
+    }
+
+    ///
+    /// Authenticate every request with a static bearer token, e.g. for Prometheus behind
+    /// an OAuth2 proxy.
+    ///
+    /// Replaces credentials set with [ProqClient::with_basic_auth]. Like those, the token is
+    /// only attached when the request is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Bearer token
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_bearer_token("eyJhbGciOiJIUzI1NiJ9");
+    ///# }
+    /// ```
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.authorization = Some(format!("Bearer {}", token));
+        self
+    }
+
+    ///
+    /// Send an extra header with every request, e.g. `X-Scope-OrgID` of multi-tenant
+    /// Cortex or Mimir.
+    ///
+    /// Header names are case-insensitive, setting a header again replaces its value.
+    /// Headers set by the request itself, like `Content-Type`, and the ones set through other
+    /// options, like `Authorization` of [ProqClient::with_bearer_token] or `Accept-Encoding`,
+    /// take precedence. Invalid header names or values are reported as
+    /// [ProqError::InvalidParameter] when sending.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Header name
+    /// * `value` - Header value
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_header("X-Scope-OrgID", "tenant-1");
+    ///# }
+    /// ```
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.set_header(name, value);
+        self
+    }
+
+    ///
+    /// Send an extra header with every request, in place version of [ProqClient::with_header].
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Header name
+    /// * `value` - Header value
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let mut client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap();
+    /// client.set_header("X-Scope-OrgID", "tenant-1");
+    ///# }
+    /// ```
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers
+            .insert(name.to_ascii_lowercase(), value.to_owned());
+    }
+
+    ///
+    /// Serve requests under a path prefix, e.g. for Prometheus started with
+    /// `--web.route-prefix=/prometheus` or behind a reverse proxy sub-path.
+    ///
+    /// Leading and trailing slashes are normalized, `prometheus`, `/prometheus/` and
+    /// `/prometheus` are the same prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `route_prefix` - Path prefix of the API endpoints
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_route_prefix("/prometheus/");
+    ///# }
+    /// ```
+    pub fn with_route_prefix(mut self, route_prefix: &str) -> Self {
+        self.route_prefix = normalize_route_prefix(route_prefix);
+        self
+    }
+
+    ///
+    /// Coalesce concurrent identical instant queries into a single request.
+    ///
+    /// Instant queries with the same query and evaluation time which are issued while such
+    /// a query is in flight don't send their own request. They all receive a copy of the
+    /// in-flight query's result instead. Nothing is cached, the next query after completion
+    /// is sent again.
+    ///
+    /// # Arguments
+    ///
+    /// * `coalesce` - Whether identical in-flight instant queries share one request
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_coalescing(true);
+    ///# }
+    /// ```
+    pub fn with_coalescing(mut self, coalesce: bool) -> Self {
+        self.in_flight = Some(Arc::default()).filter(|_| coalesce);
+        self
+    }
+
+    ///
+    /// Retry requests failing with transient errors.
+    ///
+    /// `GET` requests are retried on connection errors, truncated bodies and
+    /// `503 Service Unavailable` or `429 Too Many Requests` responses. `POST` requests are
+    /// only retried when connecting fails with [ProqError::ConnectFailed], once anything is
+    /// sent the server might have already processed them. By default requests are not retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy` - [RetryPolicy] : Number of retries and their backoff
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_retry_policy(Some(RetryPolicy {
+    ///     max_retries: 5,
+    ///     ..RetryPolicy::default()
+    /// }));
+    ///# }
+    /// ```
+    pub fn with_retry_policy(mut self, retry_policy: Option<RetryPolicy>) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    ///
+    /// Decode response bodies which are not valid UTF-8 lossily.
+    ///
+    /// By default such responses fail with [ProqError::InvalidEncoding]. When enabled, invalid
+    /// sequences are replaced with `U+FFFD` and the body is decoded as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `lossy_utf8` - Whether invalid UTF-8 is replaced instead of rejected
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_lossy_utf8(true);
+    ///# }
+    /// ```
+    pub fn with_lossy_utf8(mut self, lossy_utf8: bool) -> Self {
+        self.lossy_utf8 = lossy_utf8;
+        self
+    }
+
+    ///
+    /// Fail successful responses carrying warnings with [ProqError::Warnings].
+    ///
+    /// Warnings signal degraded results, e.g. partial data when a store of Thanos is down.
+    /// By default they are only kept in [crate::result_types::ApiOk::warnings], strict mode is meant for pipelines
+    /// which must not act on incomplete data, like alerting.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict_warnings` - Whether results with warnings are rejected
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_strict_warnings(true);
+    ///# }
+    /// ```
+    pub fn with_strict_warnings(mut self, strict_warnings: bool) -> Self {
+        self.strict_warnings = strict_warnings;
+        self
+    }
+
+    ///
+    /// Write every issued instant and range query to the given writer, for auditing.
+    ///
+    /// Each query is written as one JSON object per line once its outcome is known, with
+    /// the query text, time range, step, time of issue, duration in seconds and status,
+    /// like the Prometheus query log. Instant queries have the same start and end and a zero step.
+    /// Clones share the writer. Failures to write are ignored and don't fail the query.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - destination of the log lines
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(5)),
+    /// ).unwrap()
+    /// .with_query_log(std::io::stderr());
+    ///# }
+    /// ```
+    pub fn with_query_log(mut self, writer: impl std::io::Write + Send + 'static) -> Self {
+        self.query_log = Some(QueryLog::new(writer));
+        self
+    }
+
+    ///
+    /// Maximum query timeout sent with queries.
+    pub fn query_timeout(&self) -> Option<Duration> {
+        self.query_timeout
+    }
+
+    ///
+    /// Change the query timeout of an existing client, e.g. tighter for health checks.
+    ///
+    /// # Arguments
+    ///
+    /// * `query_timeout` - Maximum query timeout, Prometheus default if `None`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    /// let mut client = ProqClient::new(
+    ///     "localhost:9090",
+    ///     Some(Duration::from_secs(30)),
+    /// ).unwrap();
+    ///
+    /// client.set_query_timeout(Some(Duration::from_secs(2)));
+    /// assert_eq!(client.query_timeout(), Some(Duration::from_secs(2)));
+    ///# }
+    /// ```
+    pub fn set_query_timeout(&mut self, query_timeout: Option<Duration>) {
+        self.query_timeout = query_timeout;
+    }
+
+    fn resolve_range(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<(Option<UtcTime>, Option<UtcTime>)> {
+        let start_rel = match self.default_range {
+            Some(start_rel) => chrono::Duration::from_std(start_rel)
+                .map_err(|e| ProqError::InvalidParameter(format!("default range: {}", e)))?,
+            None => return Ok((start_time, end_time)),
+        };
+
+        let end = end_time.unwrap_or_else(Utc::now);
+        let start = start_time.unwrap_or(end - start_rel);
+        Ok((Some(start), Some(end)))
+    }
+
+    async fn get_basic(&self, url: Url) -> ProqResult<ApiResult> {
+        self.send(PreparedRequest::new(Method::GET, url)).await
+    }
+
+    async fn get_query(&self, endpoint: &str, query: &impl Serialize) -> ProqResult<ApiResult> {
+        self.send(self.prepare_query(endpoint, query)?).await
+    }
+
+    fn prepare_query(&self, endpoint: &str, query: &impl Serialize) -> ProqResult<PreparedRequest> {
+        let mut url: Url = Url::from_str(self.get_slug(endpoint)?.to_string().as_str())?;
+        let query = serde_urlencoded::to_string(query)
+            .map_err(|e| ProqError::HTTPClientError(Box::new(e)))?;
+        url.set_query(Some(query.as_str()));
+        Ok(PreparedRequest::new(Method::GET, url))
+    }
+
+    async fn get_encoded(&self, endpoint: &str, query: String) -> ProqResult<ApiResult> {
+        let mut url: Url = Url::from_str(self.get_slug(endpoint)?.to_string().as_str())?;
+        url.set_query(Some(query.as_str()).filter(|q| !q.is_empty()));
+        self.send(PreparedRequest::new(Method::GET, url)).await
+    }
+
+    async fn post(&self, endpoint: &str, payload: String) -> ProqResult<ApiResult> {
+        self.send(self.prepare_post(endpoint, payload)?).await
+    }
+
+    fn prepare_post(&self, endpoint: &str, payload: String) -> ProqResult<PreparedRequest> {
+        let url: Url = Url::from_str(self.get_slug(endpoint)?.to_string().as_str())?;
+        let mut request = PreparedRequest::new(Method::POST, url);
+        request.body = Some(payload);
+        request.headers.insert(
+            header::CONTENT_TYPE.to_string(),
+            mime::APPLICATION_WWW_FORM_URLENCODED.to_string(),
+        );
+        Ok(request)
+    }
+
+    async fn send(&self, request: PreparedRequest) -> ProqResult<ApiResult> {
+        self.send_with_meta(request).await.map(|(result, _)| result)
+    }
+
+    async fn send_with_meta(
+        &self,
+        request: PreparedRequest,
+    ) -> ProqResult<(ApiResult, ResponseMeta)> {
+        let entry = self
+            .query_log
+            .as_ref()
+            .zip(request.logged_query.as_ref())
+            .map(|(log, query)| log.start(query));
+        let result = async {
+            let (status, body, meta) = self.fetch(request).await?;
+            Ok((self.recv(status, &body)?, meta))
+        }
+        .await;
+        if let Some(entry) = entry {
+            entry.finish(matches!(result, Ok((ApiResult::ApiOk(_), _))));
+        }
+        result
+    }
+
+    async fn send_raw(&self, request: PreparedRequest) -> ProqResult<RawApiResult> {
+        let entry = self
+            .query_log
+            .as_ref()
+            .zip(request.logged_query.as_ref())
+            .map(|(log, query)| log.start(query));
+        let result = async {
+            let (status, body, _) = self.fetch(request).await?;
+            recv_raw(status, body)
+        }
+        .await;
+        if let Some(entry) = entry {
+            entry.finish(matches!(result, Ok(RawApiResult::ApiOk(_))));
+        }
+        result
+    }
+
+    fn recv(&self, status: StatusCode, body: &[u8]) -> ProqResult<ApiResult> {
+        match recv_maybe_json(status, body)? {
+            ApiResult::ApiOk(ok) if self.strict_warnings && ok.has_warnings() => {
+                Err(ProqError::Warnings(ok.warnings))
+            }
+            result => Ok(result),
+        }
+    }
+
+    ///
+    /// Send a request with `If-None-Match` when an entity tag is given.
+    ///
+    /// `304 Not Modified` responses are returned as [ProqError::NotModified].
+    async fn send_conditional(
+        &self,
+        mut request: PreparedRequest,
+        etag: Option<&str>,
+    ) -> ProqResult<(ApiResult, ResponseMeta)> {
+        if let Some(etag) = etag {
+            request
+                .headers
+                .insert(header::IF_NONE_MATCH.to_string(), etag.to_owned());
+        }
+
+        let (status, body, meta) = self.fetch(request).await?;
+        if status == StatusCode::NOT_MODIFIED {
+            return Err(ProqError::NotModified {
+                etag: meta.etag().or(etag).map(ToOwned::to_owned),
+            });
+        }
+        Ok((self.recv(status, &body)?, meta))
+    }
+
+    ///
+    /// Send a request to an admin endpoint, which are disabled unless Prometheus is
+    /// started with `--web.enable-admin-api`.
+    async fn send_admin(&self, request: PreparedRequest) -> ProqResult<ApiResult> {
+        let (status, body, _) = self.fetch(request).await?;
+        if status == StatusCode::FORBIDDEN {
+            return Err(ProqError::AdminApiDisabled {
+                error_message: String::from_utf8_lossy(&body).trim().to_owned(),
+            });
+        }
+
+        match self.recv(status, &body) {
+            Err(ProqError::ServerOverloaded { error_message })
+                if error_message.contains(PROQ_ADMIN_APIS_DISABLED) =>
+            {
+                Err(ProqError::AdminApiDisabled { error_message })
+            }
+            result => result,
+        }
+    }
+
+    async fn fetch(
+        &self,
+        request: PreparedRequest,
+    ) -> ProqResult<(StatusCode, Vec<u8>, ResponseMeta)> {
+        let timeout = request.timeout.or(self.query_timeout);
+        let policy = match &self.retry_policy {
+            Some(policy) => policy,
+            None => return with_deadline(self.fetch_once(request), timeout).await,
+        };
+
+        let idempotent = request.method == Method::GET;
+        let mut retry = 0;
+        loop {
+            let result = with_deadline(self.fetch_once(request.clone()), timeout).await;
+            if retry >= policy.max_retries {
+                return result;
+            }
+            // Requests which may have reached the server are only repeated if idempotent.
+            let transient = match &result {
+                Err(ProqError::ConnectFailed(_)) => true,
+                Err(ProqError::ConnectionError(_)) | Err(ProqError::Incomplete { .. }) => {
+                    idempotent
+                }
+                Ok((status, _, _)) => {
+                    idempotent
+                        && (*status == StatusCode::SERVICE_UNAVAILABLE
+                            || *status == StatusCode::TOO_MANY_REQUESTS)
+                }
+                Err(_) => false,
+            };
+            if !transient {
+                return result;
+            }
+
+            let overloaded =
+                matches!(&result, Ok((status, body, _)) if is_overloaded(*status, body));
+            Delay::new(policy.jittered_backoff(retry, overloaded)).await;
+            retry += 1;
+        }
+    }
+
+    async fn fetch_once(
+        &self,
+        request: PreparedRequest,
+    ) -> ProqResult<(StatusCode, Vec<u8>, ResponseMeta)> {
+        let query_id = request
+            .headers
+            .get(&PROQ_QUERY_ID_HEADER.to_ascii_lowercase())
+            .cloned();
+        let mut res = self.open(request).await?;
+        let mut body = read_body(&mut res, self.max_response_bytes).await?;
+        if res.status().is_success() {
+            body = utf8_body(body, self.lossy_utf8)?;
+        }
+        let mut meta = response_meta(&mut res, &body);
+        meta.query_id = query_id;
+
+        Ok((res.status(), body, meta))
+    }
+
+    async fn open(&self, mut request: PreparedRequest) -> ProqResult<surf::Response> {
+        // Header names are lowercase, headers of the request win over the custom ones
+        // and options inserted below override both.
+        for (name, value) in &self.headers {
+            request
+                .headers
+                .entry(name.clone())
+                .or_insert_with(|| value.clone());
+        }
+        if let Some(encoding) = &self.accept_encoding {
+            request
+                .headers
+                .insert(header::ACCEPT_ENCODING.to_string(), encoding.clone());
+        }
+        if let Some(prepared) = &self.prepared {
+            prepared
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_or_insert(request);
+            return Err(ProqError::GenericError(
+                "request is prepared, not sent".into(),
+            ));
+        }
+        if let Some(authorization) = &self.authorization {
+            request
+                .headers
+                .insert(header::AUTHORIZATION.to_string(), authorization.clone());
+        }
+
+        let headers = RequestHeaders::try_from(&request.headers)?;
+        let url = surf::url::Url::parse(request.url.as_str())
+            .map_err(|e| ProqError::GenericError(e.to_string()))?;
+        let mut req =
+            surf::Request::with_client(request.method.clone(), url, self.http_client.clone())
+                .middleware(headers);
+        if let Some(body) = request.body.take() {
+            req = req.body_string(body);
+        }
+        req.await.map_err(connection_error)
+    }
+
+    ///
+    /// Make an instant query to Prometheus.
+    /// Get all timeseries at that point.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let instantq = client.instant_query("up", None).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<ApiResult> {
+        self.instant_query_with_meta(query, eval_time)
+            .await
+            .map(|(result, _)| result)
+    }
+
+    ///
+    /// Make an instant query to Prometheus with a timeout overriding the client's query timeout.
+    ///
+    /// The timeout is sent to Prometheus and it is the deadline of the request as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    /// * `timeout` - query timeout of this query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let instantq = client
+    ///     .instant_query_with_timeout("count(up)", None, Duration::from_secs(120))
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query_with_timeout(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+        timeout: Duration,
+    ) -> ProqResult<ApiResult> {
+        self.send(self.prepare_instant_query(query, eval_time, Some(timeout))?)
+            .await
+    }
+
+    ///
+    /// Make an instant query to Prometheus and report transport details of the response.
+    ///
+    /// [ResponseMeta] carries both the encoded and decoded body sizes, which tells how well
+    /// responses compress. Use [ProqClient::with_accept_encoding] to request a specific encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap()
+    ///#     .with_accept_encoding(Some("gzip"));
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// if let Ok((_, meta)) = client.instant_query_with_meta("up", None).await {
+    ///     println!("compression ratio: {:?}", meta.compression_ratio());
+    /// }
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query_with_meta(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<(ApiResult, ResponseMeta)> {
+        let request = self.prepare_instant_query(query, eval_time, self.query_timeout)?;
+        match &self.in_flight {
+            Some(in_flight) => {
+                in_flight
+                    .run(request.url.to_string(), self.send_with_meta(request))
+                    .await
+            }
+            None => self.send_with_meta(request).await,
+        }
+    }
+
+    ///
+    /// Make an instant query to Prometheus tagged with an opaque request identifier.
+    ///
+    /// The identifier is sent as `X-Query-Id` header and returned in [ResponseMeta],
+    /// which ties an entry of the Prometheus query log back to the application request.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    /// * `query_id` - opaque request identifier
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let instantq = client.instant_query_with_id("up", None, "req-42").await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query_with_id(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+        query_id: &str,
+    ) -> ProqResult<(ApiResult, ResponseMeta)> {
+        let mut request = self.prepare_instant_query(query, eval_time, self.query_timeout)?;
+        request.headers.insert(
+            PROQ_QUERY_ID_HEADER.to_ascii_lowercase(),
+            query_id.to_owned(),
+        );
+        self.send_with_meta(request).await
+    }
+
+    ///
+    /// Make an instant query to Thanos Querier with Thanos specific options.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    /// * `options` - [ThanosOptions] : Thanos query options
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let options = ThanosOptions {
+    ///     dedup: Some(true),
+    ///     partial_response: Some(true),
+    ///     ..ThanosOptions::default()
+    /// };
+    /// let instantq = client.instant_query_thanos("up", None, &options).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query_thanos(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+        options: &ThanosOptions,
+    ) -> ProqResult<ApiResult> {
+        let request = self.prepare_instant_query(query, eval_time, self.query_timeout)?;
+        self.send(with_query_params(request, options)?).await
+    }
+
+    fn prepare_instant_query(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+        timeout: Option<Duration>,
+    ) -> ProqResult<PreparedRequest> {
+        let logged_query = LoggedQuery::instant(query, eval_time);
+        let query = InstantQuery {
+            query: query.into(),
+            time: eval_time.map(datetime_to_timestamp),
+            timeout: timeout.map(duration_to_timeout),
+        };
+        let mut request = self.prepare_query(PROQ_INSTANT_QUERY_URL, &query)?;
+        request.timeout = timeout;
+        request.logged_query = Some(logged_query);
+        Ok(request)
+    }
+
+    ///
+    /// Check whether the given selector matches any series at evaluation time.
+    ///
+    /// This is the client-side equivalent of alerting on `absent()`:
+    /// `false` means that no series was returned for the selector.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - series selector to look for
+    /// * `eval_time` - instant query timestamp to query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let present = client.is_present("up{job=\"prometheus\"}", None).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn is_present(
+        &self,
+        selector: &str,
+        eval_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<bool> {
+        validate_selector(selector)?;
+        let instants = self
+            .instant_query(selector, eval_time)
+            .await?
+            .into_instants()?;
+        Ok(!instants.is_empty())
+    }
+
+    ///
+    /// Make an instant query expected to yield a single value, e.g. for stat panels.
+    ///
+    /// Returns the value of the single series of a vector result or the value of a scalar result,
+    /// `default` if the vector is empty. Vectors with more than one series are reported as
+    /// [ProqError::UnexpectedData].
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    /// * `default` - value of an empty result
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let targets_up = client.scalar_or("sum(up)", None, 0.0).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn scalar_or(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+        default: f64,
+    ) -> ProqResult<f64> {
+        let result = self.instant_query(query, eval_time).await?;
+        if let Some(sample) = result.as_ok().and_then(ApiOk::as_scalar) {
+            return Ok(sample.value);
+        }
+        match result.as_instants()? {
+            [] => Ok(default),
+            [instant] => Ok(instant.sample.value),
+            instants => Err(ProqError::UnexpectedData(format!(
+                "expected at most one series, got: {}",
+                instants.len()
+            ))),
+        }
+    }
+
+    ///
+    /// Make an instant query and shape the resulting instant vector on the client.
+    ///
+    /// Samples not matching the label filter are dropped, the rest is sorted by `sort`.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    /// * `filter` - [LabelSelector] : Client-side label filter
+    /// * `sort` - [SortOrder] : Order of the returned samples
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let filter = LabelSelector::new().not_equal("job", "prometheus");
+    /// let busiest = client
+    ///     .instant_query_filtered("rate(http_requests_total[5m])", None, &filter, SortOrder::ValueDescending)
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query_filtered(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+        filter: &LabelSelector,
+        sort: SortOrder,
+    ) -> ProqResult<Vec<Instant>> {
+        let mut instants: Vec<Instant> = self
+            .instant_query(query, eval_time)
+            .await?
+            .into_instants()?
+            .into_iter()
+            .filter(|instant| filter.matches(&instant.metric))
+            .collect();
+        sort_instants(&mut instants, &sort);
+        Ok(instants)
+    }
+
+    ///
+    /// Make an instant query sorted by the value of a label on Prometheus.
+    ///
+    /// The query is wrapped in `sort_by_label` or `sort_by_label_desc`,
+    /// which are available since Prometheus 2.36. Samples are returned in the order Prometheus sent them.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `label` - label name to sort by
+    /// * `descending` - sort in descending order of the label value
+    /// * `eval_time` - instant query timestamp to query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let by_instance = client
+    ///     .instant_query_sorted_by_label("up", "instance", false, None)
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn instant_query_sorted_by_label(
+        &self,
+        query: &str,
+        label: &str,
+        descending: bool,
+        eval_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<Vec<Instant>> {
+        if !is_label_name(label) {
+            return Err(ProqError::InvalidParameter(format!(
+                "invalid label name: {}",
+                label
+            )));
+        }
+        let function = if descending {
+            "sort_by_label_desc"
+        } else {
+            "sort_by_label"
+        };
+        let query = format!("{}({}, \"{}\")", function, query, label);
+        self.instant_query(&query, eval_time).await?.into_instants()
+    }
+
+    ///
+    /// Make a range query to Prometheus.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    /// * `step` - step duration between start and end range
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::minutes(1));
+    /// let step = Some(Duration::from_secs_f64(1.5));
+    ///
+    /// let rangeq = client.range_query("up", start, Some(end), step).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn range_query(
+        &self,
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        step: Option<Duration>,
+    ) -> ProqResult<ApiResult> {
+        self.send(self.prepare_range_query(
+            query,
+            start_time,
+            end_time,
+            step,
+            self.query_timeout,
+        )?)
+        .await
+    }
+
+    ///
+    /// Make a range query to Prometheus with a timeout overriding the client's query timeout.
+    ///
+    /// The timeout is sent to Prometheus and it is the deadline of the request as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    /// * `step` - step duration between start and end range
+    /// * `timeout` - query timeout of this query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::days(7));
+    ///
+    /// let rangeq = client
+    ///     .range_query_with_timeout(
+    ///         "sum(rate(http_requests_total[5m]))",
+    ///         start,
+    ///         Some(end),
+    ///         Some(Duration::from_secs(3600)),
+    ///         Duration::from_secs(120),
+    ///     )
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn range_query_with_timeout(
+        &self,
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        step: Option<Duration>,
+        timeout: Duration,
+    ) -> ProqResult<ApiResult> {
+        self.send(self.prepare_range_query(query, start_time, end_time, step, Some(timeout))?)
+            .await
+    }
+
+    ///
+    /// Make an instant query to Prometheus and return the undecoded response body.
+    ///
+    /// Only the status of the response is inspected, the data is not deserialized.
+    /// Meant for pass-through use cases like proxies, where decoding large results is wasted.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `eval_time` - instant query timestamp to query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// if let Ok(raw) = client.raw_query("up", None).await {
+    ///     let body: Vec<u8> = raw.into_body();
+    /// }
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn raw_query(
+        &self,
+        query: &str,
+        eval_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<RawApiResult> {
+        self.send_raw(self.prepare_instant_query(query, eval_time, self.query_timeout)?)
+            .await
+    }
+
+    ///
+    /// Make a range query to Prometheus and return the undecoded response body.
+    ///
+    /// Only the status of the response is inspected, see [ProqClient::raw_query].
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    /// * `step` - step duration between start and end range
+    pub async fn raw_range_query(
+        &self,
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        step: Option<Duration>,
+    ) -> ProqResult<RawApiResult> {
+        self.send_raw(self.prepare_range_query(
+            query,
+            start_time,
+            end_time,
+            step,
+            self.query_timeout,
+        )?)
+        .await
+    }
+
+    ///
+    /// Make a range query to Thanos Querier with Thanos specific options.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    /// * `step` - step duration between start and end range
+    /// * `options` - [ThanosOptions] : Thanos query options
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::days(30));
+    /// let options = ThanosOptions {
+    ///     dedup: Some(true),
+    ///     max_source_resolution: Some("1h".into()),
+    ///     ..ThanosOptions::default()
+    /// };
+    ///
+    /// let rangeq = client
+    ///     .range_query_thanos("up", start, Some(end), Some(Duration::from_secs(3600)), &options)
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn range_query_thanos(
+        &self,
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        step: Option<Duration>,
+        options: &ThanosOptions,
+    ) -> ProqResult<ApiResult> {
+        let request =
+            self.prepare_range_query(query, start_time, end_time, step, self.query_timeout)?;
+        self.send(with_query_params(request, options)?).await
+    }
+
+    fn prepare_range_query(
+        &self,
+        query: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        step: Option<Duration>,
+        timeout: Option<Duration>,
+    ) -> ProqResult<PreparedRequest> {
+        let (start_time, end_time) = self.resolve_range(start_time, end_time)?;
+        if let (Some(start), Some(end)) = (start_time, end_time) {
+            if start > end {
+                return Err(ProqError::InvalidParameter(format!(
+                    "range start {} is after end {}",
+                    start, end
+                )));
+            }
+        }
+        if step == Some(Duration::from_secs(0)) {
+            return Err(ProqError::InvalidParameter(
+                "range step must be greater than zero".into(),
+            ));
+        }
+
+        let logged_query = LoggedQuery::range(query, start_time, end_time, step);
+        let query = RangeQuery {
+            query: query.into(),
+            start: start_time.map(datetime_to_timestamp),
+            end: end_time.map(datetime_to_timestamp),
+            step: step.map(duration_to_step),
+            timeout: timeout.map(duration_to_timeout),
+            exemplar: Some(true).filter(|_| self.range_exemplars),
+        };
+        let mut request = self.prepare_query(PROQ_RANGE_QUERY_URL, &query)?;
+        request.timeout = timeout;
+        request.logged_query = Some(logged_query);
+        Ok(request)
+    }
+
+    ///
+    /// Make a range query over the window ending now, with the step computed for the given
+    /// number of points, e.g. a 6 hour window with 300 points is queried with a 72 second step.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `window` - length of the queried range which ends now
+    /// * `points` - desired number of points in the range
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let rangeq = client
+    ///     .range_query_window("up", chrono::Duration::hours(6), 300)
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn range_query_window(
+        &self,
+        query: &str,
+        window: chrono::Duration,
+        points: u32,
+    ) -> ProqResult<ApiResult> {
+        if points == 0 {
+            return Err(ProqError::InvalidParameter(
+                "range points must be greater than zero".into(),
+            ));
+        }
+        let window_std = window.to_std().map_err(|_| {
+            ProqError::InvalidParameter(format!("range window {} is negative", window))
+        })?;
+
+        let end = Utc::now();
+        let start = end - window;
+        let step = window_std / points;
+        self.range_query(query, Some(start), Some(end), Some(step))
+            .await
+    }
+
+    ///
+    /// Query exemplars of the series selected by the query within the given time range.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = end - chrono::Duration::minutes(5);
+    ///
+    /// let exemplars = client.query_exemplars("test_exemplar_metric_total", start, end).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn query_exemplars(
+        &self,
+        query: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> ProqResult<ApiResult> {
+        if start_time > end_time {
+            return Err(ProqError::InvalidParameter(format!(
+                "exemplars start {} is after end {}",
+                start_time, end_time
+            )));
+        }
+
+        let query = ExemplarsQuery {
+            query: query.into(),
+            start: datetime_to_timestamp(start_time),
+            end: datetime_to_timestamp(end_time),
+        };
+        self.get_query(PROQ_QUERY_EXEMPLARS_URL, &query).await
+    }
+
+    ///
+    /// Get series from Prometheus
+    ///
+    /// # Arguments
+    ///
+    /// * `selectors` - vector of selectors
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::hours(1));
+    ///
+    /// let selectors = vec!["up", "process_start_time_seconds{job=\"prometheus\"}"];
+    /// let series = client.series(selectors, start, Some(end)).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn series(
+        &self,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<ApiResult> {
+        let query = self.series_payload(selectors, start_time, end_time)?;
+        self.post(PROQ_SERIES_URL, query).await
+    }
+
+    ///
+    /// Get series from Prometheus as a stream of metrics.
+    ///
+    /// Metrics are yielded as they are parsed from the response body instead of buffering
+    /// the whole result, which keeps peak memory low for huge responses.
+    /// Response size limit doesn't apply since the body is never buffered completely.
+    /// The query timeout applies to opening the response and to every read of the body.
+    ///
+    /// # Arguments
+    ///
+    /// * `selectors` - vector of selectors
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    /// use futures::StreamExt;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let mut series = Box::pin(client.series_stream(vec!["up"], None, None));
+    /// while let Some(metric) = series.next().await {
+    ///     println!("{:?}", metric);
+    /// }
+    ///#     });
+    ///# }
+    /// ```
+    pub fn series_stream<'a>(
+        &'a self,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> impl Stream<Item = ProqResult<Metric>> + 'a {
+        let request = self
+            .series_payload(selectors, start_time, end_time)
+            .and_then(|query| self.prepare_post(PROQ_SERIES_URL, query));
+
+        stream::unfold(SeriesStreamState::Start(request), move |state| async move {
+            let (mut res, mut scanner, timeout) = match state {
+                SeriesStreamState::Start(request) => match self.open_series(request).await {
+                    Ok((res, timeout)) => (res, DataArrayScanner::new(), timeout),
+                    Err(e) => return Some((Err(e), SeriesStreamState::Done)),
+                },
+                SeriesStreamState::Streaming(res, scanner, timeout) => (res, scanner, timeout),
+                SeriesStreamState::Done => return None,
+            };
+
+            let mut chunk = [0_u8; 8192];
+            loop {
+                if let Some(element) = scanner.next_element() {
+                    let metric = serde_json::from_slice::<Metric>(&element)
+                        .map_err(|e| ProqError::GenericError(e.to_string()));
+                    return Some((metric, SeriesStreamState::Streaming(res, scanner, timeout)));
+                }
+                if scanner.is_done() {
+                    return None;
+                }
+
+                // Every read is bounded, a stalled body must not block the stream forever.
+                let read = with_deadline(
+                    async {
+                        res.read(&mut chunk)
+                            .await
+                            .map_err(|e| ProqError::Incomplete {
+                                received_bytes: scanner.received_bytes(),
+                                message: e.to_string(),
+                            })
+                    },
+                    timeout,
+                )
+                .await;
+                let read = match read {
+                    Ok(0) => Err(ProqError::Incomplete {
+                        received_bytes: scanner.received_bytes(),
+                        message: "body ended before the series were complete".into(),
+                    }),
+                    Ok(read) => scanner.feed(&chunk[..read]),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = read {
+                    return Some((Err(e), SeriesStreamState::Done));
+                }
+            }
+        })
+    }
+
+    async fn open_series(
+        &self,
+        request: ProqResult<PreparedRequest>,
+    ) -> ProqResult<(surf::Response, Option<Duration>)> {
+        let request = request?;
+        let timeout = request.timeout.or(self.query_timeout);
+        let mut res = with_deadline(self.open(request), timeout).await?;
+        if res.status().is_success() {
+            return Ok((res, timeout));
+        }
+
+        let body = read_body(&mut res, self.max_response_bytes).await?;
+        Err(status_error(res.status(), &body))
+    }
+
+    fn series_payload(
+        &self,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<String> {
+        for selector in &selectors {
+            validate_selector(selector)?;
+        }
+
+        let query = SeriesRequest {
+            selectors: selectors.iter().map(|s| (*s).to_string()).collect(),
+            start: start_time.as_ref().map(DateTime::timestamp),
+            end: end_time.as_ref().map(DateTime::timestamp),
+            timeout: self.query_timeout.map(duration_to_timeout),
+        };
+
+        Ok(query.form_encode(self.match_encoding))
+    }
+
+    ///
+    /// Get series from Prometheus grouped by the selector which matched them.
+    ///
+    /// Issues one series request per selector concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `selectors` - vector of selectors
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::hours(1));
+    ///
+    /// let selectors = vec!["up", "process_start_time_seconds{job=\"prometheus\"}"];
+    /// let series = client.series_by_selector(selectors, start, Some(end)).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn series_by_selector(
+        &self,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<HashMap<String, Vec<Metric>>> {
+        let requests = selectors.iter().map(|selector| async move {
+            let metrics = self
+                .series(vec![*selector], start_time, end_time)
+                .await?
+                .into_series()?;
+            Ok::<_, ProqError>(((*selector).to_string(), metrics))
+        });
+
+        Ok(try_join_all(requests).await?.into_iter().collect())
+    }
+
+    ///
+    /// Get all label names from Prometheus.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let label_names = client.label_names().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn label_names(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_LABELS_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Get label names of the series matching the selectors within the time range.
+    ///
+    /// # Arguments
+    ///
+    /// * `selectors` - vector of selectors, all series if empty
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::hours(1));
+    ///
+    /// let label_names = client
+    ///     .label_names_filtered(vec!["up{job=\"prometheus\"}"], start, Some(end))
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn label_names_filtered(
+        &self,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<ApiResult> {
+        let query = self.series_payload(selectors, start_time, end_time)?;
+        self.post(PROQ_LABELS_URL, query).await
+    }
+
+    ///
+    /// Get all label values for a given label from Prometheus.
+    ///
+    /// # Arguments
+    ///
+    /// * `label_name` - Label name to get label values
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let label_name = "version";
+    /// let label_values = client.label_values(label_name).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn label_values(&self, label_name: &str) -> ProqResult<ApiResult> {
+        let slug = label_values_slug(label_name);
+        let url: Url = Url::from_str(self.get_slug(slug.as_str())?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Get label values for a given label of the series matching the selectors within the time range.
+    ///
+    /// # Arguments
+    ///
+    /// * `label_name` - Label name to get label values
+    /// * `selectors` - vector of selectors, all series if empty
+    /// * `start` - start time of the query
+    /// * `end` - end time of the query
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use chrono::Utc;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::hours(1));
+    ///
+    /// let instances = client
+    ///     .label_values_filtered("instance", vec!["up{job=\"node\"}"], start, Some(end))
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn label_values_filtered(
+        &self,
+        label_name: &str,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<ApiResult> {
+        for selector in &selectors {
+            validate_selector(selector)?;
+        }
+
+        let query = LabelValuesRequest {
+            selectors: selectors.iter().map(|s| (*s).to_string()).collect(),
+            start: start_time.as_ref().map(DateTime::timestamp),
+            end: end_time.as_ref().map(DateTime::timestamp),
+        };
+        let slug = label_values_slug(label_name);
+
+        self.get_encoded(slug.as_str(), query.form_encode(self.match_encoding))
+            .await
+    }
+
+    ///
+    /// Get all metric names from Prometheus.
+    ///
+    /// Metric names are the values of the `__name__` label.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let metric_names = client.metric_names().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn metric_names(&self) -> ProqResult<Vec<String>> {
+        self.label_values(PROQ_METRIC_NAME_LABEL)
+            .await?
+            .into_labels_or_values()
+    }
+
+    ///
+    /// Get metric names matching the given regular expression from Prometheus.
+    ///
+    /// Filtering is done by Prometheus with `{__name__=~"<pattern>"}` series selector.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - RE2 regular expression that metric names should fully match
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let http_metric_names = client.metric_names_matching("http_.*").await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn metric_names_matching(&self, pattern: &str) -> ProqResult<Vec<String>> {
+        let escaped = pattern.replace('\\', "\\\\").replace('"', "\\\"");
+        let query = LabelValuesRequest {
+            selectors: vec![format!("{{{}=~\"{}\"}}", PROQ_METRIC_NAME_LABEL, escaped)],
+            start: None,
+            end: None,
+        };
+        let slug = format!(PROQ_LABEL_VALUES_URL!(), PROQ_METRIC_NAME_LABEL);
+
+        self.get_encoded(slug.as_str(), query.form_encode(self.match_encoding))
+            .await?
+            .into_labels_or_values()
+    }
+
+    ///
+    /// Get all Prometheus targets.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let targets = client.targets().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn targets(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_TARGETS_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Get distinct scrape pool names of the active Prometheus targets.
+    ///
+    /// Targets without a scrape pool fall back to their `job` label.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let scrape_pools = client.scrape_pools().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn scrape_pools(&self) -> ProqResult<Vec<String>> {
+        Ok(self.targets().await?.into_targets()?.scrape_pools())
+    }
+
+    ///
+    /// Get Prometheus targets filtered by the given target state.
+    ///
+    /// Target state can be `ACTIVE`, `DROPPED` or `ANY` which are defined in [ProqTargetStates].
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - [ProqTargetStates] : State to filter
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let filtered_targets = client.targets_with_state(ProqTargetStates::DROPPED).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn targets_with_state(&self, state: ProqTargetStates) -> ProqResult<ApiResult> {
+        let query = TargetsWithStatesRequest { state };
+        self.get_query(PROQ_TARGETS_URL, &query).await
+    }
+
+    ///
+    /// Get all rules from Prometheus.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let rules = client.rules().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn rules(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_RULES_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Get rules unless they are unchanged since the response with the given entity tag.
+    ///
+    /// Entity tag of the response is available from [ResponseMeta::etag].
+    /// Unchanged rules are reported as [ProqError::NotModified].
+    ///
+    /// # Arguments
+    ///
+    /// * `etag` - entity tag of the previously fetched rules, unconditional if `None`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// if let Ok((rules, meta)) = client.rules_with_etag(None).await {
+    ///     let unchanged = client.rules_with_etag(meta.etag()).await;
+    /// }
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn rules_with_etag(
+        &self,
+        etag: Option<&str>,
+    ) -> ProqResult<(ApiResult, ResponseMeta)> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_RULES_URL)?.to_string().as_str())?;
+        self.send_conditional(PreparedRequest::new(Method::GET, url), etag)
+            .await
+    }
+
+    ///
+    /// Get rules filtered by given type.
+    ///
+    /// Type can be either `ALERT` or `RECORD` from the [ProqRulesType].
+    ///
+    /// # Arguments
+    ///
+    /// * `rule_type` - [ProqRulesType] : Rule type to filter
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let filtered_rules = client.rules_with_type(ProqRulesType::ALERT).await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn rules_with_type(&self, rule_type: ProqRulesType) -> ProqResult<ApiResult> {
+        let query = RulesWithTypeRequest { rule_type };
+        self.get_query(PROQ_RULES_URL, &query).await
+    }
+
+    ///
+    /// Get current alerts Prometheus has.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let alerts = client.alerts().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn alerts(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_ALERTS_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Get alert managers currently Prometheus has.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let alert_managers = client.alert_managers().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn alert_managers(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_ALERT_MANAGERS_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Check whether the active alert managers of Prometheus are reachable.
+    ///
+    /// Alert managers are probed concurrently with a `HEAD` request to their URL, so the alerts
+    /// aren't transferred. An alert manager is up when it responds within the query timeout of
+    /// the client, at most 5 seconds. Client errors like `405 Method Not Allowed` still count
+    /// as up, server errors (`5xx`) count as down.
+    /// Credentials and headers of the client are not sent with the probes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// if let Ok(health) = client.alertmanager_health().await {
+    ///     let down = health.iter().filter(|(_, up)| !up).count();
+    /// }
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn alertmanager_health(&self) -> ProqResult<Vec<(Url, bool)>> {
+        let alert_managers = self.alert_managers().await?.into_alert_managers()?;
+        let probes = alert_managers
+            .active
+            .into_iter()
+            .map(|alert_manager| async move {
+                let up = self.probe(&alert_manager.url).await;
+                (alert_manager.url, up)
+            });
+        Ok(join_all(probes).await)
+    }
+
+    async fn probe(&self, url: &Url) -> bool {
+        let url = match surf::url::Url::parse(url.as_str()) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+        let request = async {
+            surf::Request::with_client(Method::HEAD, url, self.http_client.clone())
+                .await
+                .map_err(connection_error)
+        };
+        let timeout = self.query_timeout.map_or(PROQ_PROBE_TIMEOUT, |timeout| {
+            timeout.min(PROQ_PROBE_TIMEOUT)
+        });
+        match with_deadline(request, Some(timeout)).await {
+            Ok(res) => !res.status().is_server_error(),
+            Err(_) => false,
+        }
+    }
+
+    ///
+    /// Query config that Prometheus configured
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let config = client.config().await;
+    ///#     });
+    ///# }
+    /// ```
+    pub async fn config(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_STATUS_CONFIG_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
+    }
+
+    ///
+    /// Get configuration unless it is unchanged since the response with the given entity tag.
+    ///
+    /// Entity tag of the response is available from [ResponseMeta::etag].
+    /// Unchanged configuration is reported as [ProqError::NotModified].
     ///
     /// # Arguments
     ///
-    /// * `host` - host port combination string: e.g. `localhost:9090`
-    /// * `query_timeout` - Maximum query timeout for the client
+    /// * `etag` - entity tag of the previously fetched configuration, unconditional if `None`
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
-    ///# use chrono::Utc;
     ///# use std::time::Duration;
     ///
     ///# fn main() {
-    /// let client = ProqClient::new(
-    ///     "localhost:9090",
-    ///     Some(Duration::from_secs(5)),
-    /// ).unwrap();
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// if let Ok((config, meta)) = client.config_with_etag(None).await {
+    ///     let unchanged = client.config_with_etag(meta.etag()).await;
+    /// }
+    ///#     });
     ///# }
     /// ```
-    pub fn new(host: &str, query_timeout: Option<Duration>) -> ProqResult<Self> {
-        Self::new_with_proto(host, ProqProtocol::HTTPS, query_timeout)
+    pub async fn config_with_etag(
+        &self,
+        etag: Option<&str>,
+    ) -> ProqResult<(ApiResult, ResponseMeta)> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_STATUS_CONFIG_URL)?.to_string().as_str())?;
+        self.send_conditional(PreparedRequest::new(Method::GET, url), etag)
+            .await
     }
 
     ///
-    /// Get a Proq client with specified protocol.
-    ///
-    /// # Arguments
-    ///
-    /// * `host` - host port combination string: e.g. `localhost:9090`
-    /// * `protocol` - [ProqProtocol] Currently either HTTP or HTTPS
-    /// * `query_timeout` - Maximum query timeout for the client
+    /// Query flag values that Prometheus configured with
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
-    ///# use chrono::Utc;
     ///# use std::time::Duration;
     ///
     ///# fn main() {
-    /// let client = ProqClient::new_with_proto(
-    ///     "localhost:9090",
-    ///     ProqProtocol::HTTP,
-    ///     Some(Duration::from_secs(5)),
-    /// ).unwrap();
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let flags = client.flags().await;
+    ///#     });
     ///# }
     /// ```
-    pub fn new_with_proto(
-        host: &str,
-        protocol: ProqProtocol,
-        query_timeout: Option<Duration>,
-    ) -> ProqResult<Self> {
-        let host = Url::from_str(host).map_err(ProqError::UrlParseError)?;
-
-        Ok(Self {
-            host,
-            query_timeout,
-            protocol,
-        })
-    }
-
-    async fn get_basic(&self, url: Url) -> ProqResult<ApiResult> {
-        surf::get(url)
-            .recv_json()
-            .await
-            .map_err(|e| ProqError::GenericError(e.to_string()))
-    }
-
-    async fn get_query(&self, endpoint: &str, query: &impl Serialize) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(&endpoint)?.to_string().as_str())?;
-        surf::get(url)
-            .set_query(&query)
-            .map_err(|e| ProqError::HTTPClientError(Box::new(e)))?
-            .recv_json()
-            .await
-            .map_err(|e| ProqError::GenericError(e.to_string()))
-    }
-
-    async fn post(&self, endpoint: &str, payload: String) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(&endpoint)?.to_string().as_str())?;
-        surf::post(url)
-            .body_string(payload)
-            .set_mime(mime::APPLICATION_WWW_FORM_URLENCODED)
-            .recv_json()
-            .await
-            .map_err(|e| ProqError::GenericError(e.to_string()))
+    pub async fn flags(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_STATUS_FLAGS_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
     }
 
     ///
-    /// Make an instant query to Prometheus.
-    /// Get all timeseries at that point.
-    ///
-    /// # Arguments
-    ///
-    /// * `query` - query string
-    /// * `eval_time` - instant query timestamp to query
+    /// Query TSDB cardinality and head block statistics
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
-    ///# use chrono::Utc;
     ///# use std::time::Duration;
     ///
     ///# fn main() {
@@ -168,38 +2964,29 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let instantq = client.instant_query("up", None).await;
+    /// let tsdb_stats = client.tsdb_stats().await;
     ///#     });
     ///# }
     /// ```
-    pub async fn instant_query(
-        &self,
-        query: &str,
-        eval_time: Option<DateTime<Utc>>,
-    ) -> ProqResult<ApiResult> {
-        let query = InstantQuery {
-            query: query.into(),
-            time: eval_time.as_ref().map(|et| DateTime::timestamp(et)),
-            timeout: self.query_timeout.map(|t| t.as_secs().to_string()),
-        };
-        self.get_query(PROQ_INSTANT_QUERY_URL, &query).await
+    pub async fn tsdb_stats(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(self.get_slug(PROQ_STATUS_TSDB_URL)?.to_string().as_str())?;
+        self.get_basic(url).await
     }
 
     ///
-    /// Make a range query to Prometheus.
+    /// Get metric metadata from Prometheus.
+    ///
+    /// Metadata is returned per metric name, independent of the targets exposing it.
     ///
     /// # Arguments
     ///
-    /// * `query` - query string
-    /// * `start` - start time of the query
-    /// * `end` - end time of the query
-    /// * `step` - step duration between start and end range
+    /// * `metric` - metric name to retrieve metadata for, all metrics if `None`
+    /// * `limit` - maximum number of metrics to return
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
-    ///# use chrono::Utc;
     ///# use std::time::Duration;
     ///
     ///# fn main() {
@@ -210,45 +2997,35 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let end = Utc::now();
-    /// let start = Some(end - chrono::Duration::minutes(1));
-    /// let step = Some(Duration::from_secs_f64(1.5));
-    ///
-    /// let rangeq = client.range_query("up", start, Some(end), step).await;
+    /// let metadata = client.metadata(Some("http_requests_total"), None).await;
     ///#     });
     ///# }
     /// ```
-    pub async fn range_query(
+    pub async fn metadata(
         &self,
-        query: &str,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-        step: Option<Duration>,
+        metric: Option<&str>,
+        limit: Option<u64>,
     ) -> ProqResult<ApiResult> {
-        let query = RangeQuery {
-            query: query.into(),
-            start: start_time.as_ref().map(|et| DateTime::timestamp(et)),
-            end: end_time.as_ref().map(|et| DateTime::timestamp(et)),
-            step: step.map(|s| s.as_secs_f64()),
-            timeout: self.query_timeout.map(|t| t.as_secs().to_string()),
+        let query = MetadataRequest {
+            metric: metric.map(ToOwned::to_owned),
+            limit,
         };
-        self.get_query(PROQ_RANGE_QUERY_URL, &query).await
+        self.get_query(PROQ_METADATA_URL, &query).await
     }
 
     ///
-    /// Get series from Prometheus
+    /// Get metric metadata of the targets currently scraped by Prometheus.
     ///
     /// # Arguments
     ///
-    /// * `selectors` - vector of selectors
-    /// * `start` - start time of the query
-    /// * `end` - end time of the query
+    /// * `match_target` - label selector of the targets, e.g. `{job="prometheus"}`, all targets if `None`
+    /// * `metric` - metric name to retrieve metadata for, all metrics if `None`
+    /// * `limit` - maximum number of targets to match
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
-    ///# use chrono::Utc;
     ///# use std::time::Duration;
     ///
     ///# fn main() {
@@ -259,45 +3036,28 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let end = Utc::now();
-    /// let start = Some(end - chrono::Duration::hours(1));
-    ///
-    /// let selectors = vec!["up", "process_start_time_seconds{job=\"prometheus\"}"];
-    /// let series = client.series(selectors, start, Some(end)).await;
+    /// let metadata = client
+    ///     .target_metadata(Some("{job=\"prometheus\"}"), Some("go_goroutines"), None)
+    ///     .await;
     ///#     });
     ///# }
     /// ```
-    pub async fn series(
+    pub async fn target_metadata(
         &self,
-        selectors: Vec<&str>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
+        match_target: Option<&str>,
+        metric: Option<&str>,
+        limit: Option<u64>,
     ) -> ProqResult<ApiResult> {
-        let query = SeriesRequest {
-            selectors: selectors.iter().map(|s| (*s).to_string()).collect(),
-            start: start_time.as_ref().map(|et| DateTime::timestamp(et)),
-            end: end_time.as_ref().map(|et| DateTime::timestamp(et)),
-            timeout: self.query_timeout.map(|t| t.as_secs().to_string()),
+        let query = TargetMetadataRequest {
+            match_target: match_target.map(ToOwned::to_owned),
+            metric: metric.map(ToOwned::to_owned),
+            limit,
         };
-
-        let mut uencser = url::form_urlencoded::Serializer::new(String::new());
-        // TODO: Remove the allocation overhead from AsRef.
-        for s in query.selectors {
-            uencser.append_pair("match[]", s.as_str());
-        }
-        query
-            .start
-            .map(|s| uencser.append_pair("start", s.to_string().as_str()));
-        query
-            .end
-            .map(|s| uencser.append_pair("end", s.to_string().as_str()));
-        let query = uencser.finish();
-
-        self.post(PROQ_SERIES_URL, query).await
+        self.get_query(PROQ_TARGETS_METADATA_URL, &query).await
     }
 
     ///
-    /// Get all label names from Prometheus.
+    /// Get build information of Prometheus, like version, revision and Go version.
     ///
     /// # Example
     ///
@@ -313,21 +3073,21 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let label_names = client.label_names().await;
+    /// let build_info = client.build_info().await;
     ///#     });
     ///# }
     /// ```
-    pub async fn label_names(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_LABELS_URL)?.to_string().as_str())?;
+    pub async fn build_info(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(
+            self.get_slug(PROQ_STATUS_BUILDINFO_URL)?
+                .to_string()
+                .as_str(),
+        )?;
         self.get_basic(url).await
     }
 
     ///
-    /// Get all label values for a given label from Prometheus.
-    ///
-    /// # Arguments
-    ///
-    /// * `label_name` - Label name to get label values
+    /// Get runtime information of Prometheus, like series and goroutine counts.
     ///
     /// # Example
     ///
@@ -343,19 +3103,21 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let label_name = "version";
-    /// let label_values = client.label_values(label_name).await;
+    /// let runtime_info = client.runtime_info().await;
     ///#     });
     ///# }
     /// ```
-    pub async fn label_values(&self, label_name: &str) -> ProqResult<ApiResult> {
-        let slug = format!(PROQ_LABEL_VALUES_URL!(), label_name);
-        let url: Url = Url::from_str(self.get_slug(slug.as_str())?.to_string().as_str())?;
+    pub async fn runtime_info(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(
+            self.get_slug(PROQ_STATUS_RUNTIMEINFO_URL)?
+                .to_string()
+                .as_str(),
+        )?;
         self.get_basic(url).await
     }
 
     ///
-    /// Get all Prometheus targets.
+    /// Get WAL replay progress of Prometheus, reported while it starts up.
     ///
     /// # Example
     ///
@@ -371,23 +3133,29 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let targets = client.targets().await;
+    /// let wal_replay = client.wal_replay().await;
     ///#     });
     ///# }
     /// ```
-    pub async fn targets(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_TARGETS_URL)?.to_string().as_str())?;
+    pub async fn wal_replay(&self) -> ProqResult<ApiResult> {
+        let url: Url = Url::from_str(
+            self.get_slug(PROQ_STATUS_WALREPLAY_URL)?
+                .to_string()
+                .as_str(),
+        )?;
         self.get_basic(url).await
     }
 
     ///
-    /// Get Prometheus targets filtered by the given target state.
+    /// Create a snapshot of all current data into `snapshots/<datetime>-<rand>` under
+    /// the TSDB data directory.
     ///
-    /// Target state can be `ACTIVE`, `DROPPED` or `ANY` which are defined in [ProqTargetStates].
+    /// This is an admin endpoint, if admin APIs are disabled [ProqError::AdminApiDisabled]
+    /// is returned.
     ///
     /// # Arguments
     ///
-    /// * `state` - [ProqTargetStates] : State to filter
+    /// * `skip_head` - Skip data present in the head block
     ///
     /// # Example
     ///
@@ -403,22 +3171,35 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let filtered_targets = client.targets_with_state(ProqTargetStates::DROPPED).await;
+    /// let snapshot = client.snapshot(false).await;
     ///#     });
     ///# }
     /// ```
-    pub async fn targets_with_state(&self, state: ProqTargetStates) -> ProqResult<ApiResult> {
-        let query = TargetsWithStatesRequest { state };
-        self.get_query(PROQ_TARGETS_URL, &query).await
+    pub async fn snapshot(&self, skip_head: bool) -> ProqResult<ApiResult> {
+        let payload = serde_urlencoded::to_string(SnapshotRequest { skip_head })
+            .map_err(|e| ProqError::HTTPClientError(Box::new(e)))?;
+        self.send_admin(self.prepare_post(PROQ_ADMIN_SNAPSHOT_URL, payload)?)
+            .await
     }
 
     ///
-    /// Get all rules from Prometheus.
+    /// Delete data of the series matching the selectors within the given time range.
+    ///
+    /// Data is only marked for deletion, see [ProqClient::clean_tombstones] to reclaim the disk space.
+    /// This is an admin endpoint, if admin APIs are disabled [ProqError::AdminApiDisabled]
+    /// is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `selectors` - vector of selectors, at least one is required
+    /// * `start` - start time of the deletion, defaults to the minimum possible time
+    /// * `end` - end time of the deletion, defaults to the maximum possible time
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
+    ///# use chrono::Utc;
     ///# use std::time::Duration;
     ///
     ///# fn main() {
@@ -429,23 +3210,48 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let rules = client.rules().await;
+    /// let end = Utc::now();
+    /// let start = Some(end - chrono::Duration::hours(1));
+    ///
+    /// let deleted = client.delete_series(vec!["up{job=\"stale\"}"], start, Some(end)).await;
     ///#     });
     ///# }
     /// ```
-    pub async fn rules(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_RULES_URL)?.to_string().as_str())?;
-        self.get_basic(url).await
+    pub async fn delete_series(
+        &self,
+        selectors: Vec<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ProqResult<()> {
+        if selectors.is_empty() {
+            return Err(ProqError::InvalidParameter(
+                "at least one selector is required to delete series".into(),
+            ));
+        }
+        for selector in &selectors {
+            validate_selector(selector)?;
+        }
+
+        let query = SeriesRequest {
+            selectors: selectors.iter().map(|s| (*s).to_string()).collect(),
+            start: start_time.as_ref().map(DateTime::timestamp),
+            end: end_time.as_ref().map(DateTime::timestamp),
+            timeout: None,
+        };
+
+        let query = query.form_encode(self.match_encoding);
+
+        self.send_admin(self.prepare_post(PROQ_ADMIN_DELETE_SERIES_URL, query)?)
+            .await?
+            .into_data()
+            .map(|_| ())
     }
 
     ///
-    /// Get rules filtered by given type.
-    ///
-    /// Type can be either `ALERT` or `RECORD` from the [ProqRulesType].
-    ///
-    /// # Arguments
+    /// Remove the deleted data from disk and clean up the existing tombstones.
     ///
-    /// * `rule_type` - [ProqRulesType] : Rule type to filter
+    /// This is an admin endpoint, if admin APIs are disabled [ProqError::AdminApiDisabled]
+    /// is returned.
     ///
     /// # Example
     ///
@@ -461,17 +3267,22 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let filtered_rules = client.rules_with_type(ProqRulesType::ALERT).await;
+    /// let cleaned = client.clean_tombstones().await;
     ///#     });
     ///# }
     /// ```
-    pub async fn rules_with_type(&self, rule_type: ProqRulesType) -> ProqResult<ApiResult> {
-        let query = RulesWithTypeRequest { rule_type };
-        self.get_query(PROQ_RULES_URL, &query).await
+    pub async fn clean_tombstones(&self) -> ProqResult<()> {
+        self.send_admin(self.prepare_post(PROQ_ADMIN_CLEAN_TOMBSTONES_URL, String::new())?)
+            .await?
+            .into_data()
+            .map(|_| ())
     }
 
     ///
-    /// Get current alerts Prometheus has.
+    /// Detect which kind of server is behind the configured host.
+    ///
+    /// Build information is queried and classified heuristically as Prometheus, Thanos,
+    /// Cortex/Mimir or VictoriaMetrics. See [crate::result_types::BuildInfo::backend_kind].
     ///
     /// # Example
     ///
@@ -487,17 +3298,23 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let alerts = client.alerts().await;
+    /// let backend = client.detect_backend().await;
     ///#     });
     ///# }
     /// ```
-    pub async fn alerts(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_ALERTS_URL)?.to_string().as_str())?;
-        self.get_basic(url).await
+    pub async fn detect_backend(&self) -> ProqResult<BackendKind> {
+        Ok(self.build_info().await?.into_build_info()?.backend_kind())
     }
 
     ///
-    /// Get alert managers currently Prometheus has.
+    /// Scrape an exposition endpoint like `/metrics` of the host.
+    ///
+    /// OpenMetrics is preferred over the Prometheus text format, the body is parsed according
+    /// to the returned `Content-Type`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the exposition endpoint, e.g. `/metrics`
     ///
     /// # Example
     ///
@@ -513,17 +3330,25 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let alert_managers = client.alert_managers().await;
+    /// let metrics = client.scrape("/metrics").await;
     ///#     });
     ///# }
     /// ```
-    pub async fn alert_managers(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_ALERT_MANAGERS_URL)?.to_string().as_str())?;
-        self.get_basic(url).await
+    pub async fn scrape(&self, path: &str) -> ProqResult<Vec<ScrapedMetric>> {
+        let url: Url = Url::from_str(self.get_slug(path)?.to_string().as_str())?;
+        self.scrape_url(url).await
     }
 
     ///
-    /// Query config that Prometheus configured
+    /// Pull the latest samples of the series matching the selectors from the federation endpoint.
+    ///
+    /// Federation is the supported way to pull a subset of metrics from another Prometheus,
+    /// it returns the exposition format instead of JSON. Every selector is sent with
+    /// its own `match[]` key regardless of [ProqClient::with_match_encoding].
+    ///
+    /// # Arguments
+    ///
+    /// * `selectors` - vector of selectors
     ///
     /// # Example
     ///
@@ -539,53 +3364,118 @@ impl ProqClient {
     ///#     ).unwrap();
     ///#
     ///#     futures::executor::block_on(async {
-    /// let config = client.config().await;
+    /// let metrics = client.federate(vec!["{job=\"prometheus\"}", "up"]).await;
     ///#     });
     ///# }
     /// ```
-    pub async fn config(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_STATUS_CONFIG_URL)?.to_string().as_str())?;
-        self.get_basic(url).await
+    pub async fn federate(&self, selectors: Vec<&str>) -> ProqResult<Vec<ScrapedMetric>> {
+        for selector in &selectors {
+            validate_selector(selector)?;
+        }
+
+        let mut url: Url = Url::from_str(self.get_slug(PROQ_FEDERATE_URL)?.to_string().as_str())?;
+        url.query_pairs_mut()
+            .extend_pairs(selectors.iter().map(|selector| ("match[]", selector)));
+        self.scrape_url(url).await
+    }
+
+    async fn scrape_url(&self, url: Url) -> ProqResult<Vec<ScrapedMetric>> {
+        let mut request = PreparedRequest::new(Method::GET, url);
+        request
+            .headers
+            .insert(header::ACCEPT.to_string(), PROQ_SCRAPE_ACCEPT.to_owned());
+
+        let (status, body, meta) = self.fetch(request).await?;
+        if !status.is_success() {
+            return Err(status_error(status, &body));
+        }
+
+        let openmetrics = meta
+            .header(header::CONTENT_TYPE.as_str())
+            .is_some_and(|t| t.starts_with(PROQ_OPENMETRICS_CONTENT_TYPE));
+        let body = String::from_utf8(body)
+            .map_err(|e| ProqError::UnexpectedData(format!("exposition body: {}", e)))?;
+        parse_exposition(&body, openmetrics)
+    }
+
+    pub(crate) fn get_slug(&self, slug: &str) -> ProqResult<Uri> {
+        uri::Builder::new()
+            .scheme(self.protocol.scheme())
+            .authority(self.host.as_str())
+            .path_and_query(
+                format!("{}/{}", self.route_prefix, slug.trim_start_matches('/')).as_str(),
+            )
+            .build()
+            .map_err(ProqError::UrlBuildError)
+    }
+}
+
+fn normalize_route_prefix(prefix: &str) -> String {
+    match prefix.trim_matches('/') {
+        "" => String::new(),
+        prefix => format!("/{}", prefix),
     }
+}
+
+///
+/// Append the serialized parameters to the query string of the request.
+fn with_query_params(
+    mut request: PreparedRequest,
+    params: &impl Serialize,
+) -> ProqResult<PreparedRequest> {
+    let params =
+        serde_urlencoded::to_string(params).map_err(|e| ProqError::HTTPClientError(Box::new(e)))?;
+    let query = match request.url.query() {
+        Some(query) if !query.is_empty() && !params.is_empty() => {
+            format!("{}&{}", query, params)
+        }
+        Some(query) if !query.is_empty() => query.to_owned(),
+        _ => params,
+    };
+    request.url.set_query(Some(query.as_str()));
+    Ok(request)
+}
+
+fn label_values_slug(label_name: &str) -> String {
+    let label_name = utf8_percent_encode(label_name, PATH_SEGMENT_ENCODE_SET);
+    format!(PROQ_LABEL_VALUES_URL!(), label_name)
+}
+
+impl TryFrom<&str> for ProqClient {
+    type Error = ProqError;
 
     ///
-    /// Query flag values that Prometheus configured with
+    /// Get a Proq client from a full URL like `https://prom.example.com:9090`.
+    ///
+    /// Protocol is detected from the URL scheme and no query timeout is set.
+    /// Path of the URL is used as route prefix, see [ProqClient::with_route_prefix].
+    /// The URL is validated like the host of [ProqClientBuilder::host].
     ///
     /// # Example
     ///
     /// ```rust
     /// use proq::prelude::*;
-    ///# use std::time::Duration;
+    /// use std::convert::TryFrom;
     ///
     ///# fn main() {
-    ///#     let client = ProqClient::new_with_proto(
-    ///#         "localhost:9090",
-    ///#         ProqProtocol::HTTP,
-    ///#         Some(Duration::from_secs(5)),
-    ///#     ).unwrap();
-    ///#
-    ///#     futures::executor::block_on(async {
-    /// let flags = client.flags().await;
-    ///#     });
+    /// let client = ProqClient::try_from("http://localhost:9090").unwrap();
     ///# }
     /// ```
-    pub async fn flags(&self) -> ProqResult<ApiResult> {
-        let url: Url = Url::from_str(self.get_slug(PROQ_STATUS_FLAGS_URL)?.to_string().as_str())?;
-        self.get_basic(url).await
-    }
-
-    pub(crate) fn get_slug(&self, slug: &str) -> ProqResult<Uri> {
-        let proto = if self.protocol == ProqProtocol::HTTP {
-            "http"
-        } else {
-            "https"
+    fn try_from(value: &str) -> ProqResult<Self> {
+        let protocol = match Url::parse(value)?.scheme() {
+            "http" => ProqProtocol::HTTP,
+            "https" => ProqProtocol::HTTPS,
+            scheme => {
+                return Err(ProqError::InvalidParameter(format!(
+                    "unsupported scheme: {}",
+                    scheme
+                )))
+            }
         };
 
-        uri::Builder::new()
-            .scheme(proto)
-            .authority(self.host.as_str())
-            .path_and_query(slug)
+        ProqClientBuilder::new()
+            .protocol(protocol)
+            .host(value)
             .build()
-            .map_err(ProqError::UrlBuildError)
     }
 }
@@ -0,0 +1,98 @@
+//!
+//! Single-flight coalescing of identical in-flight requests.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use futures::channel::oneshot;
+
+use crate::errors::*;
+use crate::result_types::{ApiResult, ResponseMeta};
+
+type Outcome = ProqResult<(ApiResult, ResponseMeta)>;
+
+///
+/// Requests currently in flight, keyed by their URL.
+///
+/// The first caller of a key sends the request, callers arriving while it is in flight
+/// wait for its outcome instead of sending the same request again.
+#[derive(Default)]
+pub(crate) struct InFlight {
+    waiters: Mutex<HashMap<String, Vec<oneshot::Sender<Outcome>>>>,
+}
+
+impl InFlight {
+    pub(crate) async fn run(&self, key: String, request: impl Future<Output = Outcome>) -> Outcome {
+        loop {
+            let waiting = {
+                let mut waiters = self.lock();
+                match waiters.get_mut(&key) {
+                    Some(senders) => {
+                        let (tx, rx) = oneshot::channel();
+                        senders.push(tx);
+                        Some(rx)
+                    }
+                    None => {
+                        waiters.insert(key.clone(), Vec::new());
+                        None
+                    }
+                }
+            };
+
+            match waiting {
+                Some(rx) => match rx.await {
+                    Ok(outcome) => return outcome,
+                    // Leading request is dropped before it completed, take over.
+                    Err(oneshot::Canceled) => continue,
+                },
+                None => {
+                    let mut leader = Leader {
+                        in_flight: self,
+                        key: &key,
+                        done: false,
+                    };
+                    let outcome = request.await;
+                    for tx in leader.finish() {
+                        let _ = tx.send(duplicate(&outcome));
+                    }
+                    return outcome;
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<oneshot::Sender<Outcome>>>> {
+        self.waiters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+///
+/// Removes the key of the leading request, also when it is dropped before completion.
+struct Leader<'a> {
+    in_flight: &'a InFlight,
+    key: &'a str,
+    done: bool,
+}
+
+impl Leader<'_> {
+    fn finish(&mut self) -> Vec<oneshot::Sender<Outcome>> {
+        self.done = true;
+        self.in_flight.lock().remove(self.key).unwrap_or_default()
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.in_flight.lock().remove(self.key);
+        }
+    }
+}
+
+fn duplicate(outcome: &Outcome) -> Outcome {
+    match outcome {
+        Ok(response) => Ok(response.clone()),
+        Err(e) => Err(e.duplicate()),
+    }
+}
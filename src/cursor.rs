@@ -0,0 +1,175 @@
+//!
+//! Incremental range fetching over long time spans.
+//!
+//! A [RangeCursor] splits a long range query into chunks and keeps track of the last
+//! successfully fetched timestamp so an interrupted backfill can resume from there.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::api::ProqClient;
+use crate::errors::*;
+use crate::result_types::ApiResult;
+
+///
+/// Cursor over a range query which is fetched chunk by chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeCursor {
+    query: String,
+    next_start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: chrono::Duration,
+    chunk: chrono::Duration,
+    position: Option<DateTime<Utc>>,
+}
+
+impl RangeCursor {
+    ///
+    /// Create a cursor over `[start, end]` fetching at most `chunk` long windows.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `start` - start time of the whole range
+    /// * `end` - end time of the whole range
+    /// * `step` - step duration between samples
+    /// * `chunk` - length of a single fetched window, at least `step`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    ///#
+    ///#     futures::executor::block_on(async {
+    /// let end = Utc::now();
+    /// let start = end - chrono::Duration::days(7);
+    /// let mut cursor = RangeCursor::new(
+    ///     "up",
+    ///     start,
+    ///     end,
+    ///     Duration::from_secs(60),
+    ///     Duration::from_secs(6 * 60 * 60),
+    /// ).unwrap();
+    ///
+    /// while let Some(Ok(chunk)) = cursor.next(&client).await {
+    ///     // Persist `cursor.position()` to resume later.
+    /// }
+    ///#     });
+    ///# }
+    /// ```
+    pub fn new(
+        query: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: Duration,
+        chunk: Duration,
+    ) -> ProqResult<Self> {
+        if step == Duration::from_secs(0) {
+            return Err(ProqError::InvalidParameter(
+                "range step must be greater than zero".into(),
+            ));
+        }
+        if chunk < step {
+            return Err(ProqError::InvalidParameter(
+                "cursor chunk must not be shorter than step".into(),
+            ));
+        }
+        if start > end {
+            return Err(ProqError::InvalidParameter(format!(
+                "range start {} is after end {}",
+                start, end
+            )));
+        }
+
+        let to_chrono = |d: Duration| {
+            chrono::Duration::from_std(d).map_err(|e| ProqError::InvalidParameter(e.to_string()))
+        };
+
+        Ok(Self {
+            query: query.into(),
+            next_start: start,
+            end,
+            step: to_chrono(step)?,
+            chunk: to_chrono(chunk)?,
+            position: None,
+        })
+    }
+
+    ///
+    /// Create a cursor which resumes after the last successfully fetched timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - query string
+    /// * `position` - last successfully fetched timestamp, see [RangeCursor::position]
+    /// * `end` - end time of the whole range
+    /// * `step` - step duration between samples
+    /// * `chunk` - length of a single fetched window, at least `step`
+    pub fn resume(
+        query: &str,
+        position: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: Duration,
+        chunk: Duration,
+    ) -> ProqResult<Self> {
+        let mut cursor = Self::new(query, position.min(end), end, step, chunk)?;
+        cursor.next_start = position + cursor.step;
+        cursor.position = Some(position);
+        Ok(cursor)
+    }
+
+    ///
+    /// Last successfully fetched timestamp, `None` if nothing is fetched yet.
+    pub fn position(&self) -> Option<DateTime<Utc>> {
+        self.position
+    }
+
+    ///
+    /// Whether the whole range is fetched.
+    pub fn is_done(&self) -> bool {
+        self.next_start > self.end
+    }
+
+    ///
+    /// Start and end of the window which is fetched next.
+    pub fn next_window(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.is_done() {
+            return None;
+        }
+        let end = (self.next_start + self.chunk - self.step).min(self.end);
+        Some((self.next_start, end))
+    }
+
+    ///
+    /// Fetch the next window with the given client.
+    ///
+    /// Cursor only advances when the request succeeds, failed windows, including responses
+    /// with error status, are fetched again on the next call.
+    /// Returns `None` when the whole range is fetched.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - client to run the range query with
+    pub async fn next(&mut self, client: &ProqClient) -> Option<ProqResult<ApiResult>> {
+        let (start, end) = self.next_window()?;
+        let step = self.step.to_std().ok();
+        let result = client
+            .range_query(self.query.as_str(), Some(start), Some(end), step)
+            .await;
+
+        if let Ok(ApiResult::ApiOk(_)) = result {
+            self.position = Some(end);
+            self.next_start = end + self.step;
+        }
+        Some(result)
+    }
+}
@@ -2,29 +2,141 @@
 //! Proq client related error listing
 //!
 //! All errors are aggregated here and exposed by the Proq will be seen here.
-
-use failure::*;
 use std::result;
+use std::time::Duration;
+use thiserror::Error;
 use url::ParseError;
 
 /// Alias type for Result with Proq errors.
 pub type ProqResult<T> = result::Result<T, ProqError>;
 
 /// Error types of Proq
-#[derive(Fail, Debug)]
+#[derive(Error, Debug)]
 pub enum ProqError {
     /// Generic Error raised from Proq.
-    #[fail(display = "Generic Error: {}", _0)]
+    #[error("Generic Error: {0}")]
     GenericError(String),
     /// URL parsing error.
-    #[fail(display = "Failed to parse URL: {}", _0)]
-    UrlParseError(ParseError),
+    #[error("Failed to parse URL: {0}")]
+    UrlParseError(#[source] ParseError),
     /// URL building error.
-    #[fail(display = "Failed to build URL: {}", _0)]
-    UrlBuildError(http::Error),
+    #[error("Failed to build URL: {0}")]
+    UrlBuildError(#[source] http::Error),
     /// HTTP Client error raised from underlying HTTP client.
-    #[fail(display = "Http client Error: {}", _0)]
-    HTTPClientError(surf::Exception),
+    #[error("Http client Error: {0}")]
+    HTTPClientError(#[source] surf::Exception),
+    /// Connection to the server could not be established and nothing was sent,
+    /// e.g. the connection was refused or the host could not be resolved.
+    #[error("Failed to connect: {0}")]
+    ConnectFailed(String),
+    /// Request failed after connecting, it may have been sent partially or completely,
+    /// e.g. the connection was reset before a response was received.
+    #[error("Connection error: {0}")]
+    ConnectionError(String),
+    /// No response was received within the request deadline, see [crate::api::ProqClient::new].
+    #[error("Request timed out after {timeout:?}")]
+    Timeout {
+        /// Deadline of the request
+        timeout: Duration,
+    },
+    /// Scheme of the host doesn't match the protocol of the client, e.g. `http://` host with HTTPS.
+    #[error("Host scheme {scheme} doesn't match the client protocol {protocol}")]
+    ProtocolMismatch {
+        /// Scheme given with the host
+        scheme: String,
+        /// Scheme of the client protocol
+        protocol: String,
+    },
+    /// TLS certificate given to the client can't be read or parsed.
+    #[error("Invalid TLS certificate: {0}")]
+    InvalidCertificate(String),
+    /// Request parameters are not valid.
+    #[error("Invalid request parameter: {0}")]
+    InvalidParameter(String),
+    /// Series selector syntax is not valid.
+    #[error("Invalid selector at position {position}: {message}")]
+    InvalidSelector {
+        /// Byte position of the problem in the selector
+        position: usize,
+        /// Description of the problem
+        message: String,
+    },
+    /// Prometheus responded with error status.
+    #[error("Prometheus API error ({error_type}): {error_message}")]
+    ApiError {
+        /// Error type reported by Prometheus
+        error_type: String,
+        /// Error message reported by Prometheus
+        error_message: String,
+    },
+    /// Prometheus is overloaded and rejects the query, e.g. due to `query.max-concurrency`.
+    ///
+    /// Raised for `503 Service Unavailable` responses with `unavailable` error type.
+    /// Requests failing with this error should be retried with a longer backoff.
+    #[error("Prometheus server is overloaded: {error_message}")]
+    ServerOverloaded {
+        /// Error message reported by Prometheus
+        error_message: String,
+    },
+    /// Admin endpoint is rejected since Prometheus is not started with `--web.enable-admin-api`.
+    ///
+    /// Raised for `403 Forbidden` responses and `admin APIs disabled` errors of admin endpoints.
+    #[error("Prometheus admin APIs are disabled: {error_message}")]
+    AdminApiDisabled {
+        /// Error message of the response
+        error_message: String,
+    },
+    /// Response body ended before it was complete, e.g. the connection dropped mid-response.
+    ///
+    /// Unlike malformed responses, requests failing with this error are worth retrying.
+    #[error("Incomplete response body after {received_bytes} bytes: {message}")]
+    Incomplete {
+        /// Number of body bytes received before the body ended
+        received_bytes: usize,
+        /// Description of the problem
+        message: String,
+    },
+    /// Resource is not modified since the entity tag sent with a conditional request.
+    ///
+    /// Raised for `304 Not Modified` responses, the previously fetched result is still valid.
+    #[error("Resource is not modified, entity tag: {etag:?}")]
+    NotModified {
+        /// Entity tag of the unchanged resource
+        etag: Option<String>,
+    },
+    /// Prometheus responded with a non-success HTTP status.
+    ///
+    /// The raw body is kept, e.g. `422 Unprocessable Entity` responses of invalid queries
+    /// carry the error reported by Prometheus. Decoded, raw and streamed calls all fail
+    /// with this error, whether the body is a Prometheus error or not.
+    #[error("Unexpected HTTP status {status}: {body}")]
+    UnexpectedStatus {
+        /// HTTP status code of the response
+        status: u16,
+        /// Raw body of the response
+        body: String,
+    },
+    /// Response body is not valid UTF-8, e.g. due to a misconfigured backend or proxy.
+    ///
+    /// Bodies can be decoded lossily instead, see [crate::api::ProqClient::with_lossy_utf8].
+    #[error("Response body is not valid UTF-8 after {valid_up_to} bytes")]
+    InvalidEncoding {
+        /// Length of the valid UTF-8 prefix of the body
+        valid_up_to: usize,
+    },
+    /// Successful response carries warnings while strict warnings are enabled,
+    /// see [crate::api::ProqClient::with_strict_warnings].
+    #[error("Response has warnings: {}", .0.join("; "))]
+    Warnings(Vec<String>),
+    /// Response data is not the expected kind for the request.
+    #[error("Unexpected response data: {0}")]
+    UnexpectedData(String),
+    /// Response body exceeds the configured maximum size.
+    #[error("Response body exceeds the limit of {limit} bytes")]
+    ResponseTooLarge {
+        /// Configured maximum response size in bytes
+        limit: usize,
+    },
 }
 
 impl From<ParseError> for ProqError {
@@ -32,3 +144,65 @@ impl From<ParseError> for ProqError {
         ProqError::UrlParseError(e)
     }
 }
+
+impl ProqError {
+    ///
+    /// Copy of the error, e.g. for every caller of a coalesced request.
+    ///
+    /// Errors of the underlying HTTP client can't be copied, they are kept as [ProqError::GenericError].
+    pub(crate) fn duplicate(&self) -> ProqError {
+        match self {
+            ProqError::GenericError(message) => ProqError::GenericError(message.clone()),
+            ProqError::UrlParseError(e) => ProqError::UrlParseError(*e),
+            ProqError::UrlBuildError(_) | ProqError::HTTPClientError(_) => {
+                ProqError::GenericError(self.to_string())
+            }
+            ProqError::ConnectFailed(message) => ProqError::ConnectFailed(message.clone()),
+            ProqError::ConnectionError(message) => ProqError::ConnectionError(message.clone()),
+            ProqError::Timeout { timeout } => ProqError::Timeout { timeout: *timeout },
+            ProqError::ProtocolMismatch { scheme, protocol } => ProqError::ProtocolMismatch {
+                scheme: scheme.clone(),
+                protocol: protocol.clone(),
+            },
+            ProqError::InvalidCertificate(message) => {
+                ProqError::InvalidCertificate(message.clone())
+            }
+            ProqError::InvalidParameter(message) => ProqError::InvalidParameter(message.clone()),
+            ProqError::InvalidSelector { position, message } => ProqError::InvalidSelector {
+                position: *position,
+                message: message.clone(),
+            },
+            ProqError::ApiError {
+                error_type,
+                error_message,
+            } => ProqError::ApiError {
+                error_type: error_type.clone(),
+                error_message: error_message.clone(),
+            },
+            ProqError::ServerOverloaded { error_message } => ProqError::ServerOverloaded {
+                error_message: error_message.clone(),
+            },
+            ProqError::AdminApiDisabled { error_message } => ProqError::AdminApiDisabled {
+                error_message: error_message.clone(),
+            },
+            ProqError::Incomplete {
+                received_bytes,
+                message,
+            } => ProqError::Incomplete {
+                received_bytes: *received_bytes,
+                message: message.clone(),
+            },
+            ProqError::NotModified { etag } => ProqError::NotModified { etag: etag.clone() },
+            ProqError::UnexpectedStatus { status, body } => ProqError::UnexpectedStatus {
+                status: *status,
+                body: body.clone(),
+            },
+            ProqError::InvalidEncoding { valid_up_to } => ProqError::InvalidEncoding {
+                valid_up_to: *valid_up_to,
+            },
+            ProqError::Warnings(warnings) => ProqError::Warnings(warnings.clone()),
+            ProqError::UnexpectedData(message) => ProqError::UnexpectedData(message.clone()),
+            ProqError::ResponseTooLarge { limit } => ProqError::ResponseTooLarge { limit: *limit },
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//!
+//! Parser of the Prometheus text and OpenMetrics exposition formats.
+//!
+//! Used by [crate::api::ProqClient::scrape] to read `/metrics` like endpoints.
+
+use std::collections::HashMap;
+
+use crate::errors::*;
+
+const PROQ_FAMILY_SUFFIXES: [&str; 8] = [
+    "_bucket", "_count", "_sum", "_total", "_created", "_info", "_gcount", "_gsum",
+];
+
+///
+/// Type of a metric family declared with `# TYPE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+    GaugeHistogram,
+    Summary,
+    Info,
+    StateSet,
+    Unknown,
+}
+
+impl MetricKind {
+    fn parse(kind: &str) -> Self {
+        match kind {
+            "counter" => MetricKind::Counter,
+            "gauge" => MetricKind::Gauge,
+            "histogram" => MetricKind::Histogram,
+            "gaugehistogram" => MetricKind::GaugeHistogram,
+            "summary" => MetricKind::Summary,
+            "info" => MetricKind::Info,
+            "stateset" => MetricKind::StateSet,
+            _ => MetricKind::Unknown,
+        }
+    }
+}
+
+///
+/// Single sample of a scraped exposition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScrapedMetric {
+    /// Sample name, including suffixes like `_bucket` or `_total`
+    pub name: String,
+    /// Labels of the sample
+    pub labels: HashMap<String, String>,
+    /// Sample value
+    pub value: f64,
+    /// Timestamp of the sample in milliseconds since epoch, if exposed
+    pub timestamp: Option<i64>,
+    /// Type declared for the metric family, [MetricKind::Unknown] if undeclared
+    pub kind: MetricKind,
+    /// Help text declared for the metric family
+    pub help: Option<String>,
+}
+
+#[derive(Default)]
+struct Family {
+    kind: Option<MetricKind>,
+    help: Option<String>,
+}
+
+///
+/// Parse an exposition body into samples.
+///
+/// Timestamps of the text format are milliseconds, of OpenMetrics seconds; both are returned
+/// as milliseconds. Exemplars of OpenMetrics samples are skipped.
+///
+/// # Arguments
+///
+/// * `body` - exposition body
+/// * `openmetrics` - whether the body is in OpenMetrics format
+///
+/// # Example
+///
+/// ```rust
+/// use proq::exposition::{parse_exposition, MetricKind};
+///
+/// let body = "# TYPE up gauge\nup{job=\"prometheus\"} 1\n";
+/// let metrics = parse_exposition(body, false).unwrap();
+///
+/// assert_eq!(metrics[0].kind, MetricKind::Gauge);
+/// assert_eq!(metrics[0].value, 1.0);
+/// ```
+pub fn parse_exposition(body: &str, openmetrics: bool) -> ProqResult<Vec<ScrapedMetric>> {
+    let mut families: HashMap<String, Family> = HashMap::new();
+    let mut samples = Vec::new();
+
+    for (number, line) in body.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, ' ');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("EOF"), None, None) if openmetrics => break,
+                (Some("TYPE"), Some(name), Some(kind)) => {
+                    families.entry(name.to_owned()).or_default().kind =
+                        Some(MetricKind::parse(kind.trim()));
+                }
+                (Some("HELP"), Some(name), help) => {
+                    families.entry(name.to_owned()).or_default().help =
+                        Some(unescape_help(help.unwrap_or_default()));
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let sample = parse_sample(line, openmetrics).map_err(|message| {
+            ProqError::UnexpectedData(format!("exposition line {}: {}", number + 1, message))
+        })?;
+        samples.push(sample);
+    }
+
+    for sample in &mut samples {
+        if let Some(family) = family_of(&families, &sample.name) {
+            sample.kind = family.kind.unwrap_or(MetricKind::Unknown);
+            sample.help = family.help.clone();
+        }
+    }
+    Ok(samples)
+}
+
+fn family_of<'a>(families: &'a HashMap<String, Family>, name: &str) -> Option<&'a Family> {
+    families.get(name).or_else(|| {
+        PROQ_FAMILY_SUFFIXES
+            .iter()
+            .filter_map(|suffix| name.strip_suffix(suffix))
+            .find_map(|family| families.get(family))
+    })
+}
+
+fn parse_sample(line: &str, openmetrics: bool) -> Result<ScrapedMetric, String> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let name = &line[..name_end];
+    if name.is_empty() {
+        return Err("missing metric name".into());
+    }
+
+    let (labels, rest) = match line[name_end..].strip_prefix('{') {
+        Some(labels) => parse_labels(labels)?,
+        None => (HashMap::new(), &line[name_end..]),
+    };
+    // OpenMetrics exemplars follow the sample after ` # `.
+    let rest = match rest.find(" # ") {
+        Some(exemplar) if openmetrics => &rest[..exemplar],
+        _ => rest,
+    };
+
+    let mut fields = rest.split_whitespace();
+    let value = fields
+        .next()
+        .ok_or("missing sample value")?
+        .parse::<f64>()
+        .map_err(|e| format!("invalid sample value: {}", e))?;
+    let timestamp = fields
+        .next()
+        .map(|ts| parse_timestamp(ts, openmetrics))
+        .transpose()?;
+    if fields.next().is_some() {
+        return Err("unexpected content after timestamp".into());
+    }
+
+    Ok(ScrapedMetric {
+        name: name.to_owned(),
+        labels,
+        value,
+        timestamp,
+        kind: MetricKind::Unknown,
+        help: None,
+    })
+}
+
+fn parse_timestamp(timestamp: &str, openmetrics: bool) -> Result<i64, String> {
+    if openmetrics {
+        timestamp
+            .parse::<f64>()
+            .map(|secs| (secs * 1000.0).round() as i64)
+            .map_err(|e| format!("invalid timestamp: {}", e))
+    } else {
+        timestamp
+            .parse::<i64>()
+            .map_err(|e| format!("invalid timestamp: {}", e))
+    }
+}
+
+///
+/// Parse labels following the opening brace, returns the labels and the rest of the line.
+fn parse_labels(input: &str) -> Result<(HashMap<String, String>, &str), String> {
+    let mut labels = HashMap::new();
+    let mut rest = input.trim_start();
+
+    loop {
+        if let Some(after) = rest.strip_prefix('}') {
+            return Ok((labels, after));
+        }
+
+        let eq = rest.find('=').ok_or("missing `=` in label")?;
+        let name = rest[..eq].trim();
+        if name.is_empty() {
+            return Err("missing label name".into());
+        }
+        rest = rest[eq + 1..]
+            .trim_start()
+            .strip_prefix('"')
+            .ok_or_else(|| format!("unquoted value of label {}", name))?;
+
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        let end = loop {
+            match chars.next() {
+                Some((i, '"')) => break i,
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, c)) => value.push(c),
+                    None => return Err(format!("unterminated value of label {}", name)),
+                },
+                Some((_, c)) => value.push(c),
+                None => return Err(format!("unterminated value of label {}", name)),
+            }
+        };
+        labels.insert(name.to_owned(), value);
+
+        rest = rest[end + 1..].trim_start();
+        if let Some(after) = rest.strip_prefix(',') {
+            rest = after.trim_start();
+        } else if !rest.starts_with('}') {
+            return Err(format!("expected `,` or `}}` after label {}", name));
+        }
+    }
+}
+
+fn unescape_help(help: &str) -> String {
+    let mut unescaped = String::with_capacity(help.len());
+    let mut chars = help.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
@@ -0,0 +1,89 @@
+//!
+//! Conversions between [Duration], [DateTime] and the wire formats of the Prometheus HTTP API.
+//!
+//! Useful when building requests manually, e.g. from [crate::query_types::RangeQuery].
+
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+const PROQ_DURATION_UNITS: [(&str, u128); 7] = [
+    ("y", 365 * 24 * 60 * 60 * 1000),
+    ("w", 7 * 24 * 60 * 60 * 1000),
+    ("d", 24 * 60 * 60 * 1000),
+    ("h", 60 * 60 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+///
+/// Format a duration as range query step, in seconds as floating point number.
+///
+/// # Arguments
+///
+/// * `step` - step duration
+///
+/// # Example
+///
+/// ```rust
+/// use proq::format::duration_to_step;
+/// use std::time::Duration;
+///
+/// assert_eq!(duration_to_step(Duration::from_millis(1500)), 1.5);
+/// ```
+pub fn duration_to_step(step: Duration) -> f64 {
+    step.as_secs_f64()
+}
+
+///
+/// Format a point in time as Unix timestamp in seconds, keeping millisecond precision.
+///
+/// # Arguments
+///
+/// * `time` - point in time to format
+///
+/// # Example
+///
+/// ```rust
+/// use chrono::{TimeZone, Utc};
+/// use proq::format::datetime_to_timestamp;
+///
+/// let time = Utc.timestamp_millis_opt(1_435_781_451_781).unwrap();
+/// assert_eq!(datetime_to_timestamp(time), 1_435_781_451.781);
+/// ```
+pub fn datetime_to_timestamp(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 1000.0
+}
+
+///
+/// Format a duration as Prometheus duration string, e.g. `1m30s` or `250ms`.
+///
+/// Prometheus durations have millisecond resolution, anything below is truncated.
+///
+/// # Arguments
+///
+/// * `timeout` - timeout duration
+///
+/// # Example
+///
+/// ```rust
+/// use proq::format::duration_to_timeout;
+/// use std::time::Duration;
+///
+/// assert_eq!(duration_to_timeout(Duration::from_secs(90)), "1m30s");
+/// ```
+pub fn duration_to_timeout(timeout: Duration) -> String {
+    let mut millis = timeout.as_millis();
+    if millis == 0 {
+        return "0s".to_owned();
+    }
+
+    let mut formatted = String::new();
+    for (unit, unit_millis) in PROQ_DURATION_UNITS.iter() {
+        if millis >= *unit_millis {
+            formatted.push_str(&format!("{}{}", millis / unit_millis, unit));
+            millis %= unit_millis;
+        }
+    }
+    formatted
+}
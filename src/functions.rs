@@ -0,0 +1,405 @@
+//!
+//! Client-side implementations of PromQL functions over query results.
+//!
+//! These helpers work on already fetched results. They are useful when raw series are
+//! fetched once and evaluated multiple times locally.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::result_types::{Instant, Metric, Sample};
+use crate::value_types::prometheus_types::*;
+
+///
+/// Order of instant vector samples, see [sort_instants].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SortOrder {
+    /// Keep the order returned by Prometheus
+    Unsorted,
+    /// Ascending by sample value, like PromQL `sort`
+    ValueAscending,
+    /// Descending by sample value, like PromQL `sort_desc`
+    ValueDescending,
+    /// Ascending by the value of the label, missing labels first
+    Label(String),
+}
+
+///
+/// Sort instant vector samples in place.
+///
+/// `NaN` values are sorted last for both value orders. Sort is stable, samples with
+/// equal keys keep their order.
+///
+/// # Arguments
+///
+/// * `instants` - samples to sort
+/// * `order` - [SortOrder] : Sort key and direction
+pub fn sort_instants(instants: &mut [Instant], order: &SortOrder) {
+    let value_order = |a: f64, b: f64| match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    };
+
+    match order {
+        SortOrder::Unsorted => {}
+        SortOrder::ValueAscending => {
+            instants.sort_by(|a, b| value_order(a.sample.value, b.sample.value))
+        }
+        SortOrder::ValueDescending => instants.sort_by(|a, b| {
+            if a.sample.value.is_nan() || b.sample.value.is_nan() {
+                value_order(a.sample.value, b.sample.value)
+            } else {
+                value_order(b.sample.value, a.sample.value)
+            }
+        }),
+        SortOrder::Label(label) => {
+            instants.sort_by(|a, b| a.metric.labels.get(label).cmp(&b.metric.labels.get(label)))
+        }
+    }
+}
+
+///
+/// Buckets of a single histogram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// Labels of the bucket series without the `le` label
+    pub labels: HashMap<String, String>,
+    /// Upper bound and cumulative count of each bucket sorted by upper bound
+    pub buckets: Vec<(f64, f64)>,
+}
+
+impl Histogram {
+    ///
+    /// Calculate the φ-quantile (0 ≤ φ ≤ 1) of this histogram.
+    ///
+    /// See [histogram_quantile] for details.
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        bucket_quantile(quantile, &mut self.buckets.clone())
+    }
+}
+
+///
+/// Group `*_bucket` series into histograms by their labels other than `le`.
+///
+/// Series without a valid `le` label are ignored. Buckets of every histogram are sorted
+/// by their upper bound, `+Inf` bucket being the last. Histograms are returned in the order
+/// their first bucket appears.
+///
+/// # Arguments
+///
+/// * `buckets` - bucket series of one or more histograms
+pub fn group_histograms(buckets: Vec<Instant>) -> Vec<Histogram> {
+    let mut index: BTreeMap<Vec<(String, String)>, usize> = BTreeMap::new();
+    let mut histograms: Vec<Histogram> = Vec::new();
+
+    for bucket in buckets {
+        let mut labels = bucket.metric.labels;
+        let le = match labels
+            .remove(PROQ_BUCKET_LABEL)
+            .and_then(|le| parse_bucket_bound(&le))
+        {
+            Some(le) => le,
+            None => continue,
+        };
+
+        let mut key: Vec<(String, String)> =
+            labels.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        key.sort();
+
+        let position = *index.entry(key).or_insert_with(|| {
+            histograms.push(Histogram {
+                labels,
+                buckets: Vec::new(),
+            });
+            histograms.len() - 1
+        });
+        histograms[position].buckets.push((le, bucket.sample.value));
+    }
+
+    for histogram in histograms.iter_mut() {
+        histogram
+            .buckets
+            .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    histograms
+}
+
+///
+/// Value change of a series present in both compared vectors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeriesDelta {
+    /// Labels of the series
+    pub labels: HashMap<String, String>,
+    /// Value in the baseline vector
+    pub baseline: f64,
+    /// Value in the current vector
+    pub current: f64,
+}
+
+impl SeriesDelta {
+    ///
+    /// Difference of the current value to the baseline value.
+    pub fn delta(&self) -> f64 {
+        self.current - self.baseline
+    }
+}
+
+///
+/// Difference between two instant vectors.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VectorDiff {
+    /// Series only present in the current vector
+    pub added: Vec<Instant>,
+    /// Series only present in the baseline vector
+    pub removed: Vec<Instant>,
+    /// Value changes of series present in both vectors, including unchanged ones
+    pub deltas: Vec<SeriesDelta>,
+}
+
+impl VectorDiff {
+    ///
+    /// Deltas of the series whose value changed.
+    pub fn changed(&self) -> Vec<&SeriesDelta> {
+        self.deltas.iter().filter(|d| d.delta() != 0.0).collect()
+    }
+}
+
+///
+/// Compare two instant vectors, matching series by their complete label set.
+///
+/// Added and removed series keep the order of their vector, deltas follow the baseline order.
+///
+/// # Arguments
+///
+/// * `baseline` - vector to compare against
+/// * `current` - vector to compare
+pub fn diff_vectors(baseline: &[Instant], current: &[Instant]) -> VectorDiff {
+    let current_index: BTreeMap<Vec<(&String, &String)>, &Instant> =
+        current.iter().map(|i| (label_key(i), i)).collect();
+    let baseline_index: BTreeMap<Vec<(&String, &String)>, &Instant> =
+        baseline.iter().map(|i| (label_key(i), i)).collect();
+
+    let mut diff = VectorDiff::default();
+    for instant in baseline {
+        match current_index.get(&label_key(instant)) {
+            Some(current) => diff.deltas.push(SeriesDelta {
+                labels: instant.metric.labels.clone(),
+                baseline: instant.sample.value,
+                current: current.sample.value,
+            }),
+            None => diff.removed.push(instant.clone()),
+        }
+    }
+    diff.added = current
+        .iter()
+        .filter(|i| !baseline_index.contains_key(&label_key(i)))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+///
+/// Aggregation operator of [aggregate_vector].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AggOp {
+    /// Sum of the values, like PromQL `sum`
+    Sum,
+    /// Arithmetic mean of the values, like PromQL `avg`
+    Avg,
+    /// Smallest value, like PromQL `min`
+    Min,
+    /// Largest value, like PromQL `max`
+    Max,
+    /// Number of samples, like PromQL `count`
+    Count,
+}
+
+///
+/// Aggregate an instant vector grouped by the given labels, like PromQL's `<op> by (<labels>)`.
+///
+/// Resulting samples only carry the grouping labels which are present on the series,
+/// metric name is dropped. Groups are returned in the order their first sample appears.
+///
+/// `NaN` is handled the same way as in PromQL: it propagates through `sum` and `avg`,
+/// `min` and `max` only return `NaN` if every value of the group is `NaN`, and `count`
+/// counts `NaN` samples as well.
+///
+/// # Arguments
+///
+/// * `vector` - samples to aggregate
+/// * `op` - [AggOp] : Aggregation operator
+/// * `by` - labels to group by, all samples form a single group if empty
+pub fn aggregate_vector(vector: &[Instant], op: AggOp, by: &[&str]) -> Vec<Instant> {
+    let mut index: BTreeMap<Vec<(&str, &String)>, usize> = BTreeMap::new();
+    let mut groups: Vec<(&Instant, Vec<f64>)> = Vec::new();
+
+    for instant in vector {
+        let key: Vec<(&str, &String)> = by
+            .iter()
+            .filter_map(|name| instant.metric.labels.get(*name).map(|v| (*name, v)))
+            .collect();
+        let position = *index.entry(key).or_insert_with(|| {
+            groups.push((instant, Vec::new()));
+            groups.len() - 1
+        });
+        groups[position].1.push(instant.sample.value);
+    }
+
+    groups
+        .into_iter()
+        .map(|(first, values)| {
+            let labels = by
+                .iter()
+                .filter_map(|name| {
+                    first
+                        .metric
+                        .labels
+                        .get(*name)
+                        .map(|v| ((*name).to_owned(), v.clone()))
+                })
+                .collect();
+            Instant {
+                metric: Metric { labels },
+                sample: Sample {
+                    epoch: first.sample.epoch,
+                    value: aggregate_values(op, &values),
+                },
+            }
+        })
+        .collect()
+}
+
+fn aggregate_values(op: AggOp, values: &[f64]) -> f64 {
+    let sum: f64 = values.iter().sum();
+    match op {
+        AggOp::Sum => sum,
+        AggOp::Avg => sum / values.len() as f64,
+        AggOp::Min => values.iter().fold(f64::NAN, |acc, v| acc.min(*v)),
+        AggOp::Max => values.iter().fold(f64::NAN, |acc, v| acc.max(*v)),
+        AggOp::Count => values.len() as f64,
+    }
+}
+
+fn label_key(instant: &Instant) -> Vec<(&String, &String)> {
+    let mut key: Vec<(&String, &String)> = instant.metric.labels.iter().collect();
+    key.sort();
+    key
+}
+
+///
+/// Calculate the φ-quantile (0 ≤ φ ≤ 1) from the buckets of a histogram.
+///
+/// Works the same way as PromQL's `histogram_quantile`. Buckets are given as `*_bucket`
+/// series of a single histogram with their `le` label. Series without a valid `le`
+/// label are ignored. Linear interpolation is applied within the bucket which the quantile
+/// falls into. If the quantile falls into the `+Inf` bucket, upper bound of the second
+/// highest bucket is returned.
+///
+/// Returns `None` where PromQL would return `NaN`: if there is no `+Inf` bucket,
+/// there are less than two buckets or there are no observations.
+///
+/// # Arguments
+///
+/// * `buckets` - bucket series of a single histogram
+/// * `quantile` - quantile to calculate
+///
+/// # Example
+///
+/// ```rust
+/// use proq::prelude::*;
+///
+/// fn bucket(le: &str, count: f64) -> Instant {
+///     let mut labels = std::collections::HashMap::new();
+///     labels.insert("le".to_owned(), le.to_owned());
+///     Instant {
+///         metric: Metric { labels },
+///         sample: Sample { epoch: 1435781451.781, value: count },
+///     }
+/// }
+///
+/// let buckets = vec![bucket("0.5", 50.0), bucket("1", 100.0), bucket("+Inf", 100.0)];
+/// assert_eq!(histogram_quantile(&buckets, 0.5), Some(0.5));
+/// ```
+pub fn histogram_quantile(buckets: &[Instant], quantile: f64) -> Option<f64> {
+    let mut buckets: Vec<(f64, f64)> = buckets
+        .iter()
+        .filter_map(|b| {
+            b.metric
+                .labels
+                .get(PROQ_BUCKET_LABEL)
+                .and_then(|le| parse_bucket_bound(le))
+                .map(|le| (le, b.sample.value))
+        })
+        .collect();
+
+    bucket_quantile(quantile, &mut buckets)
+}
+
+pub(crate) fn parse_bucket_bound(le: &str) -> Option<f64> {
+    match le {
+        PROQ_INFINITY | PROQ_POSITIVE_INFINITY => Some(f64::INFINITY),
+        PROQ_NEGATIVE_INFINITY => Some(f64::NEG_INFINITY),
+        _ => le.parse::<f64>().ok(),
+    }
+}
+
+pub(crate) fn bucket_quantile(quantile: f64, buckets: &mut [(f64, f64)]) -> Option<f64> {
+    if quantile.is_nan() {
+        return None;
+    }
+    if quantile < 0.0 {
+        return Some(f64::NEG_INFINITY);
+    }
+    if quantile > 1.0 {
+        return Some(f64::INFINITY);
+    }
+
+    buckets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    match buckets.last() {
+        Some((le, _)) if *le == f64::INFINITY => {}
+        _ => return None,
+    }
+    if buckets.len() < 2 {
+        return None;
+    }
+
+    // Counts can be non-monotonic due to scrapes of buckets happening at different times.
+    let mut max = f64::NEG_INFINITY;
+    for bucket in buckets.iter_mut() {
+        max = max.max(bucket.1);
+        bucket.1 = max;
+    }
+
+    let observations = buckets[buckets.len() - 1].1;
+    if observations == 0.0 {
+        return None;
+    }
+
+    let mut rank = quantile * observations;
+    let b = buckets
+        .iter()
+        .position(|(_, count)| *count >= rank)
+        .unwrap_or(buckets.len() - 1);
+
+    if b == buckets.len() - 1 {
+        return Some(buckets[buckets.len() - 2].0);
+    }
+    if b == 0 && buckets[0].0 <= 0.0 {
+        return Some(buckets[0].0);
+    }
+
+    let mut bucket_start = 0.0;
+    let bucket_end = buckets[b].0;
+    let mut count = buckets[b].1;
+    if b > 0 {
+        bucket_start = buckets[b - 1].0;
+        count -= buckets[b - 1].1;
+        rank -= buckets[b - 1].1;
+    }
+
+    Some(bucket_start + (bucket_end - bucket_start) * (rank / count))
+}
@@ -0,0 +1,104 @@
+//!
+//! Conversion of Grafana datasource queries into Proq queries.
+//!
+//! Grafana sends the query with its panel interval, maximum data points and time range,
+//! these are turned into the arguments of [crate::api::ProqClient::range_query].
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// Maximum number of points Prometheus returns per series of a range query.
+const PROQ_MAX_POINTS: u64 = 11_000;
+
+///
+/// Query of a Grafana datasource request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrafanaQuery {
+    /// PromQL query of the panel
+    pub expr: String,
+    /// Interval between points as calculated by Grafana
+    pub interval: Duration,
+    /// Maximum number of data points the panel can show, unbounded if zero
+    pub max_data_points: u64,
+    /// Start of the panel time range
+    pub from: DateTime<Utc>,
+    /// End of the panel time range
+    pub to: DateTime<Utc>,
+}
+
+///
+/// Arguments of a [crate::api::ProqClient::range_query] call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeQueryParams {
+    /// Query string
+    pub query: String,
+    /// Start time of the query
+    pub start: Option<DateTime<Utc>>,
+    /// End time of the query
+    pub end: Option<DateTime<Utc>>,
+    /// Step duration between start and end range
+    pub step: Option<Duration>,
+}
+
+impl GrafanaQuery {
+    ///
+    /// Compute the step of the range query.
+    ///
+    /// Step is the Grafana interval, widened so the time range doesn't yield more than
+    /// `max_data_points` points, nor more than Prometheus returns for a single series.
+    /// The step is rounded up to whole milliseconds.
+    pub fn step(&self) -> Duration {
+        let range = (self.to - self.from).to_std().unwrap_or_default();
+        let max_points = match self.max_data_points {
+            0 => PROQ_MAX_POINTS,
+            max_data_points => max_data_points.min(PROQ_MAX_POINTS),
+        };
+        let range_millis = range.as_millis() as u64;
+        let min_step = Duration::from_millis(range_millis.div_ceil(max_points));
+
+        self.interval.max(min_step)
+    }
+
+    ///
+    /// Convert the query into [crate::api::ProqClient::range_query] arguments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    /// use proq::grafana::GrafanaQuery;
+    ///# use std::time::Duration;
+    ///
+    ///# fn main() {
+    ///#     let client = ProqClient::new_with_proto(
+    ///#         "localhost:9090",
+    ///#         ProqProtocol::HTTP,
+    ///#         Some(Duration::from_secs(5)),
+    ///#     ).unwrap();
+    /// let to = Utc::now();
+    /// let query = GrafanaQuery {
+    ///     expr: "up".to_string(),
+    ///     interval: Duration::from_secs(15),
+    ///     max_data_points: 1000,
+    ///     from: to - chrono::Duration::hours(6),
+    ///     to,
+    /// };
+    ///
+    /// let params = query.range_params();
+    ///#     futures::executor::block_on(async {
+    /// let rangeq = client
+    ///     .range_query(&params.query, params.start, params.end, params.step)
+    ///     .await;
+    ///#     });
+    ///# }
+    /// ```
+    pub fn range_params(&self) -> RangeQueryParams {
+        RangeQueryParams {
+            query: self.expr.clone(),
+            start: Some(self.from),
+            end: Some(self.to),
+            step: Some(self.step()),
+        }
+    }
+}
@@ -38,9 +38,19 @@
 #![forbid(unsafe_code)]
 
 pub mod api;
+mod coalesce;
+pub mod cursor;
 pub mod errors;
+pub mod exposition;
+pub mod format;
+pub mod functions;
+pub mod grafana;
+mod query_log;
 pub mod query_types;
 pub mod result_types;
+pub mod selector;
+pub mod time;
+mod transport;
 pub mod value_types;
 
 pub mod prelude {
@@ -49,9 +59,15 @@ pub mod prelude {
     //!
     //! Includes all request response types to client itself.
     pub use super::api::*;
+    pub use super::cursor::*;
     pub use super::errors::*;
+    pub use super::exposition::*;
+    pub use super::format::*;
+    pub use super::functions::*;
     pub use super::query_types::*;
     pub use super::result_types::*;
+    pub use super::selector::*;
+    pub use super::time::*;
     pub use super::value_types::prometheus_types::*;
     pub use chrono::prelude::*;
 }
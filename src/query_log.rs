@@ -0,0 +1,150 @@
+//!
+//! Audit log of issued queries.
+//!
+//! Every instant and range query is written as one JSON object per line, in the shape of
+//! the Prometheus query log:
+//!
+//! ```json
+//! {"params":{"query":"up","start":"...","end":"...","step":15.0},"ts":"...","duration":0.012,"status":"success"}
+//! ```
+
+use std::fmt;
+use std::io::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+///
+/// Writer receiving the query log lines, shared by the clones of a client.
+#[derive(Clone)]
+pub(crate) struct QueryLog {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl fmt::Debug for QueryLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("QueryLog")
+    }
+}
+
+///
+/// Parameters of an instant or range query, carried by its prepared request.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LoggedQuery {
+    query: String,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    step: Option<Duration>,
+}
+
+impl LoggedQuery {
+    ///
+    /// Instant query, logged with the same start and end and a zero step.
+    pub(crate) fn instant(query: &str, eval_time: Option<DateTime<Utc>>) -> Self {
+        Self {
+            query: query.to_owned(),
+            start: eval_time,
+            end: eval_time,
+            step: None,
+        }
+    }
+
+    pub(crate) fn range(
+        query: &str,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        step: Option<Duration>,
+    ) -> Self {
+        Self {
+            query: query.to_owned(),
+            start,
+            end,
+            step,
+        }
+    }
+}
+
+///
+/// Query which is being sent, written to the log once its outcome is known.
+pub(crate) struct QueryLogEntry {
+    log: QueryLog,
+    params: QueryLogParams,
+    ts: DateTime<Utc>,
+    started: Instant,
+}
+
+#[derive(Serialize)]
+struct QueryLogParams {
+    query: String,
+    start: String,
+    end: String,
+    step: f64,
+}
+
+#[derive(Serialize)]
+struct QueryLogLine<'a> {
+    params: &'a QueryLogParams,
+    ts: String,
+    duration: f64,
+    status: &'static str,
+}
+
+impl QueryLog {
+    pub(crate) fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    ///
+    /// Start logging the query.
+    ///
+    /// Missing start or end, e.g. of instant queries without evaluation time, are logged
+    /// as the time of issue.
+    pub(crate) fn start(&self, query: &LoggedQuery) -> QueryLogEntry {
+        let ts = Utc::now();
+        let params = QueryLogParams {
+            query: query.query.clone(),
+            start: format_time(query.start.unwrap_or(ts)),
+            end: format_time(query.end.unwrap_or(ts)),
+            step: query.step.map_or(0.0, |step| step.as_secs_f64()),
+        };
+
+        QueryLogEntry {
+            log: self.clone(),
+            params,
+            ts,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl QueryLogEntry {
+    ///
+    /// Write the log line of the query.
+    ///
+    /// Failures to write are ignored, logging must not fail the query.
+    pub(crate) fn finish(self, success: bool) {
+        let line = QueryLogLine {
+            params: &self.params,
+            ts: format_time(self.ts),
+            duration: self.started.elapsed().as_secs_f64(),
+            status: if success { "success" } else { "error" },
+        };
+        if let Ok(mut line) = serde_json::to_vec(&line) {
+            line.push(b'\n');
+            let mut writer = self
+                .log
+                .writer
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let _ = writer.write_all(&line).and_then(|_| writer.flush());
+        }
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -1,6 +1,44 @@
 //!
 //! Request types that are sent by the Proq to different endpoints.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use http::Method;
 use serde::*;
+use url::Url;
+
+use crate::query_log::LoggedQuery;
+
+///
+/// Fully built HTTP request which is sent to Prometheus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedRequest {
+    /// HTTP method of the request
+    pub method: Method,
+    /// Full request URL including the query string
+    pub url: Url,
+    /// Request body if there is any
+    pub body: Option<String>,
+    /// Request headers, keyed by lowercase header name
+    pub headers: HashMap<String, String>,
+    /// Deadline of the whole request, the client's query timeout is used if absent
+    pub timeout: Option<Duration>,
+    /// Parameters written to the query log, only set for instant and range queries
+    pub(crate) logged_query: Option<LoggedQuery>,
+}
+
+impl PreparedRequest {
+    pub(crate) fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            body: None,
+            headers: HashMap::new(),
+            timeout: None,
+            logged_query: None,
+        }
+    }
+}
 
 ///
 /// Instant query request struct
@@ -8,8 +46,8 @@ use serde::*;
 pub struct InstantQuery {
     /// PromQL Query which will be sent to API
     pub query: String,
-    /// Evaluation timestamp in unix timestamp format
-    pub time: Option<i64>,
+    /// Evaluation timestamp in unix timestamp format, in seconds with fractional part
+    pub time: Option<f64>,
     /// Timeout duration for evaluating the result
     pub timeout: Option<String>,
 }
@@ -20,14 +58,79 @@ pub struct InstantQuery {
 pub struct RangeQuery {
     /// PromQL Query which will be sent to API
     pub query: String,
-    /// Start timestamp for the range query
-    pub start: Option<i64>,
-    /// End timestamp for the range query
-    pub end: Option<i64>,
+    /// Start timestamp for the range query, in seconds with fractional part
+    pub start: Option<f64>,
+    /// End timestamp for the range query, in seconds with fractional part
+    pub end: Option<f64>,
     /// Step as duration in the range in seconds as 64-bit floating point format
     pub step: Option<f64>,
     /// Timeout duration for evaluating the result
     pub timeout: Option<String>,
+    /// Request exemplars along with the samples, only understood by some gateways
+    pub exemplar: Option<bool>,
+}
+
+///
+/// Thanos specific query options, understood by Thanos Querier in addition to the Prometheus API.
+///
+/// Only the options which are set are sent, the server defaults apply to the others.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ThanosOptions {
+    /// Deduplicate series of replicas by the replica labels, Thanos deduplicates if absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedup: Option<bool>,
+    /// Return partial results when some store APIs are unavailable, instead of an error.
+    /// The server's `--query.partial-response` setting applies if absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_response: Option<bool>,
+    /// Maximum resolution of the downsampled data to use, e.g. `5m` or `1h`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_source_resolution: Option<String>,
+    /// Query engine to evaluate the query with, `thanos` or `prometheus`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+}
+
+///
+/// Exemplars query request struct
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExemplarsQuery {
+    /// PromQL Query which will be sent to API
+    pub query: String,
+    /// Start timestamp for the exemplars query, in seconds with fractional part
+    pub start: f64,
+    /// End timestamp for the exemplars query, in seconds with fractional part
+    pub end: f64,
+}
+
+///
+/// Metric metadata request struct
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetadataRequest {
+    /// Metric name to retrieve metadata for, all metrics if absent
+    pub metric: Option<String>,
+    /// Maximum number of metrics to return
+    pub limit: Option<u64>,
+}
+
+///
+/// Per-target metric metadata request struct
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TargetMetadataRequest {
+    /// Label selector of the targets, all targets if absent
+    pub match_target: Option<String>,
+    /// Metric name to retrieve metadata for, all metrics if absent
+    pub metric: Option<String>,
+    /// Maximum number of targets to match
+    pub limit: Option<u64>,
+}
+
+///
+/// Snapshot admin request struct
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SnapshotRequest {
+    /// Skip data present in the head block
+    pub skip_head: bool,
 }
 
 ///
@@ -45,6 +148,93 @@ pub struct SeriesRequest {
     pub timeout: Option<String>,
 }
 
+impl SeriesRequest {
+    ///
+    /// Encode the request as `application/x-www-form-urlencoded` body.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - [ProqMatchEncoding] : How multiple selectors are written
+    pub fn form_encode(&self, encoding: ProqMatchEncoding) -> String {
+        form_encode_selectors(&self.selectors, self.start, self.end, encoding)
+    }
+}
+
+///
+/// Label values request struct
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LabelValuesRequest {
+    /// List of series selectors to filter the series which label values are read from
+    #[serde(rename(serialize = "match[]"))]
+    pub selectors: Vec<String>,
+    /// Start timestamp of the series which label values are read from
+    pub start: Option<i64>,
+    /// End timestamp of the series which label values are read from
+    pub end: Option<i64>,
+}
+
+impl LabelValuesRequest {
+    ///
+    /// Encode the request as URL query string.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - [ProqMatchEncoding] : How multiple selectors are written
+    pub fn form_encode(&self, encoding: ProqMatchEncoding) -> String {
+        form_encode_selectors(&self.selectors, self.start, self.end, encoding)
+    }
+}
+
+///
+/// Encode series selectors with the optional time range they are matched in.
+fn form_encode_selectors(
+    selectors: &[String],
+    start: Option<i64>,
+    end: Option<i64>,
+    encoding: ProqMatchEncoding,
+) -> String {
+    let mut uencser = url::form_urlencoded::Serializer::new(String::new());
+    append_selectors(&mut uencser, selectors, encoding);
+    if let Some(s) = start {
+        uencser.append_pair("start", s.to_string().as_str());
+    }
+    if let Some(e) = end {
+        uencser.append_pair("end", e.to_string().as_str());
+    }
+    uencser.finish()
+}
+
+fn append_selectors(
+    uencser: &mut url::form_urlencoded::Serializer<String>,
+    selectors: &[String],
+    encoding: ProqMatchEncoding,
+) {
+    if selectors.is_empty() {
+        return;
+    }
+    match encoding {
+        ProqMatchEncoding::Repeated => {
+            for s in selectors {
+                uencser.append_pair("match[]", s.as_str());
+            }
+        }
+        ProqMatchEncoding::CommaJoined => {
+            uencser.append_pair("match[]", selectors.join(",").as_str());
+        }
+    }
+}
+
+///
+/// Encoding style of `match[]` parameter for multi-selector requests.
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub enum ProqMatchEncoding {
+    /// Every selector is sent with its own `match[]` key. This is what Prometheus expects.
+    #[default]
+    Repeated,
+    /// All selectors are joined with comma and sent with a single `match[]` key.
+    CommaJoined,
+}
+
 ///
 /// Possible Prometheus target states.
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone)]
@@ -5,22 +5,28 @@
 //! https://github.com/allengeorge/prometheus-query/blob/master/src/messages.rs
 //!
 //! extended with filtered and unfiltered methods and new beta endpoints.
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::ops::Index;
 use std::result::Result as StdResult;
 use std::str::FromStr;
+use std::time::Duration;
 
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use http::header;
 use serde::{
     de,
     de::{MapAccess, SeqAccess, Unexpected, Visitor},
     ser::{SerializeStruct, SerializeTuple},
     {Deserialize, Deserializer, Serialize, Serializer},
 };
+use sha2::{Digest, Sha256};
 use url::Url;
 use url_serde::{De, Ser};
 
+use crate::errors::*;
 use crate::value_types::prometheus_types::*;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -32,6 +38,447 @@ pub enum ApiResult {
     ApiErr(ApiErr),
 }
 
+impl ApiResult {
+    ///
+    /// Extract label names or label values from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_labels_or_values(self) -> ProqResult<Vec<String>> {
+        match self.into_data()? {
+            Some(Data::LabelsOrValues(LabelsOrValues(values))) => Ok(values),
+            // Empty arrays are deserialized as series since it is the first array variant.
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Ok(Vec::new()),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected labels or values, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract targets from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_targets(self) -> ProqResult<Targets> {
+        match self.into_data()? {
+            Some(Data::Targets(targets)) => Ok(targets),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected targets, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract alert managers from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_alert_managers(self) -> ProqResult<AlertManagers> {
+        match self.into_data()? {
+            Some(Data::AlertManagers(alert_managers)) => Ok(alert_managers),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected alert managers, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract instant vector samples from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_instants(self) -> ProqResult<Vec<Instant>> {
+        match self.into_data()? {
+            Some(Data::Expression(Expression::Instant(instants))) => Ok(instants),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected instant vector, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract exemplar series from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_exemplars(self) -> ProqResult<Vec<ExemplarSet>> {
+        match self.into_data()? {
+            Some(Data::Exemplars(series)) => Ok(series),
+            // Empty arrays are deserialized as series since it is the first array variant.
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Ok(Vec::new()),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected exemplars, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract per-target metric metadata from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_target_metadata(self) -> ProqResult<Vec<TargetMetadataEntry>> {
+        match self.into_data()? {
+            Some(Data::TargetMetadata(entries)) => Ok(entries),
+            // Empty arrays are deserialized as series since it is the first array variant.
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Ok(Vec::new()),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected target metadata, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract build information from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_build_info(self) -> ProqResult<BuildInfo> {
+        match self.into_data()? {
+            Some(Data::BuildInfo(build_info)) => Ok(build_info),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected build info, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract the loaded configuration from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_config(self) -> ProqResult<Config> {
+        match self.into_data()? {
+            Some(Data::Config(config)) => Ok(config),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected config, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Extract series metrics from the result.
+    ///
+    /// Error status is returned as [ProqError::ApiError].
+    pub fn into_series(self) -> ProqResult<Vec<Metric>> {
+        match self.into_data()? {
+            Some(Data::Series(Series(metrics))) => Ok(metrics),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected series, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    pub(crate) fn into_data(self) -> ProqResult<Option<Data>> {
+        Ok(self.into_result()?.data)
+    }
+
+    ///
+    /// Borrow label names or label values of the result, see [ApiResult::into_labels_or_values].
+    pub fn as_labels_or_values(&self) -> ProqResult<&[String]> {
+        match self.data()? {
+            Some(Data::LabelsOrValues(LabelsOrValues(values))) => Ok(values),
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Ok(&[]),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected labels or values, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow targets of the result, see [ApiResult::into_targets].
+    pub fn as_targets(&self) -> ProqResult<&Targets> {
+        match self.data()? {
+            Some(Data::Targets(targets)) => Ok(targets),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected targets, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow alert managers of the result, see [ApiResult::into_alert_managers].
+    pub fn as_alert_managers(&self) -> ProqResult<&AlertManagers> {
+        match self.data()? {
+            Some(Data::AlertManagers(alert_managers)) => Ok(alert_managers),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected alert managers, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow instant vector samples of the result, see [ApiResult::into_instants].
+    pub fn as_instants(&self) -> ProqResult<&[Instant]> {
+        match self.data()? {
+            Some(Data::Expression(Expression::Instant(instants))) => Ok(instants),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected instant vector, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow exemplar series of the result, see [ApiResult::into_exemplars].
+    pub fn as_exemplars(&self) -> ProqResult<&[ExemplarSet]> {
+        match self.data()? {
+            Some(Data::Exemplars(series)) => Ok(series),
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Ok(&[]),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected exemplars, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow per-target metric metadata of the result, see [ApiResult::into_target_metadata].
+    pub fn as_target_metadata(&self) -> ProqResult<&[TargetMetadataEntry]> {
+        match self.data()? {
+            Some(Data::TargetMetadata(entries)) => Ok(entries),
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Ok(&[]),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected target metadata, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow build information of the result, see [ApiResult::into_build_info].
+    pub fn as_build_info(&self) -> ProqResult<&BuildInfo> {
+        match self.data()? {
+            Some(Data::BuildInfo(build_info)) => Ok(build_info),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected build info, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    ///
+    /// Borrow series metrics of the result, see [ApiResult::into_series].
+    pub fn as_series(&self) -> ProqResult<&[Metric]> {
+        match self.data()? {
+            Some(Data::Series(Series(metrics))) => Ok(metrics),
+            data => Err(ProqError::UnexpectedData(format!(
+                "expected series, got: {:?}",
+                data
+            ))),
+        }
+    }
+
+    fn data(&self) -> ProqResult<Option<&Data>> {
+        match self {
+            ApiResult::ApiOk(ok) => Ok(ok.data.as_ref()),
+            ApiResult::ApiErr(err) => Err(ProqError::ApiError {
+                error_type: err.error_type.clone(),
+                error_message: err.error_message.clone(),
+            }),
+        }
+    }
+
+    ///
+    /// Warnings of the result, regardless of its status.
+    pub fn warnings(&self) -> &[String] {
+        match self {
+            ApiResult::ApiOk(ok) => &ok.warnings,
+            ApiResult::ApiErr(err) => &err.warnings,
+        }
+    }
+
+    ///
+    /// Borrow the result of a success status.
+    pub fn as_ok(&self) -> Option<&ApiOk> {
+        match self {
+            ApiResult::ApiOk(ok) => Some(ok),
+            ApiResult::ApiErr(_) => None,
+        }
+    }
+
+    ///
+    /// Borrow the error of an error status.
+    pub fn as_err(&self) -> Option<&ApiErr> {
+        match self {
+            ApiResult::ApiOk(_) => None,
+            ApiResult::ApiErr(err) => Some(err),
+        }
+    }
+
+    ///
+    /// Convert into a [Result] of the success or error status.
+    ///
+    /// Partial data of errors stays accessible with [ApiErr::partial_data].
+    /// Errors convert to [ProqError::ApiError] with `?`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///
+    /// fn data(result: ApiResult) -> ProqResult<Option<Data>> {
+    ///     Ok(result.into_result()?.data)
+    /// }
+    /// ```
+    // Error is as large as success, boxing it would only cost an allocation.
+    #[allow(clippy::result_large_err)]
+    pub fn into_result(self) -> StdResult<ApiOk, ApiErr> {
+        match self {
+            ApiResult::ApiOk(ok) => Ok(ok),
+            ApiResult::ApiErr(err) => Err(err),
+        }
+    }
+
+    ///
+    /// Interpret a scalar result as boolean.
+    ///
+    /// Scalar `1` is `true`, scalar `0` is `false`. Anything else is `None`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ApiResult::ApiOk(ApiOk {
+                data: Some(Data::Expression(Expression::Scalar(sample))),
+                ..
+            }) => {
+                if sample.value == 1.0 {
+                    Some(true)
+                } else if sample.value == 0.0 {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+///
+/// Response body which is classified by its status only, the data is left undecoded.
+///
+/// Meant for pass-through use cases like proxies, see [crate::api::ProqClient::raw_query].
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawApiResult {
+    /// Body of a response with `success` status
+    ApiOk(Vec<u8>),
+    /// Body of a response with `error` status
+    ApiErr(Vec<u8>),
+}
+
+impl RawApiResult {
+    ///
+    /// Whether the response has `success` status.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, RawApiResult::ApiOk(_))
+    }
+
+    ///
+    /// Raw JSON body of the response.
+    pub fn body(&self) -> &[u8] {
+        match self {
+            RawApiResult::ApiOk(body) | RawApiResult::ApiErr(body) => body,
+        }
+    }
+
+    ///
+    /// Take the raw JSON body of the response.
+    pub fn into_body(self) -> Vec<u8> {
+        match self {
+            RawApiResult::ApiOk(body) | RawApiResult::ApiErr(body) => body,
+        }
+    }
+}
+
+///
+/// Transport level details of a response.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResponseMeta {
+    /// `Content-Encoding` of the response, if any.
+    pub content_encoding: Option<String>,
+    /// Size of the encoded body on the wire, known when the server sent `Content-Length`.
+    pub compressed_bytes: Option<usize>,
+    /// Size of the decoded body.
+    pub decompressed_bytes: usize,
+    /// Request identifier sent with the request, see [crate::api::ProqClient::instant_query_with_id].
+    pub query_id: Option<String>,
+    /// Response headers keyed by lowercase name, repeated headers are joined with `, `.
+    pub headers: HashMap<String, String>,
+}
+
+impl ResponseMeta {
+    ///
+    /// Value of the response header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    ///
+    /// Entity tag of the response, which can be sent back to make a conditional request.
+    pub fn etag(&self) -> Option<&str> {
+        self.header(header::ETAG.as_str())
+    }
+
+    ///
+    /// Ratio of decoded to encoded body size, e.g. `4.0` when the body compressed to a quarter.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match self.compressed_bytes {
+            Some(compressed) if compressed > 0 => {
+                Some(self.decompressed_bytes as f64 / compressed as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+///
+/// Category of a warning string returned alongside successful results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Warning {
+    /// Some data sources failed to answer, the result may be incomplete.
+    PartialData,
+    /// Results were dropped or truncated due to a limit.
+    Truncated,
+    /// The query used an experimental feature.
+    Experimental,
+    /// Any other warning, kept verbatim.
+    Other(String),
+}
+
+impl Warning {
+    ///
+    /// Categorize a Prometheus warning string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::result_types::Warning;
+    ///
+    /// assert_eq!(
+    ///     Warning::parse("results truncated due to limit"),
+    ///     Warning::Truncated
+    /// );
+    /// ```
+    pub fn parse(warning: &str) -> Self {
+        let lowered = warning.to_lowercase();
+        if lowered.contains("truncated") || lowered.contains("dropped due to") {
+            Warning::Truncated
+        } else if lowered.contains("experimental") {
+            Warning::Experimental
+        } else if lowered.contains("partial") || lowered.contains("remote read") {
+            Warning::PartialData
+        } else {
+            Warning::Other(warning.to_owned())
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct ApiOk {
     #[serde(default)]
@@ -40,6 +487,154 @@ pub struct ApiOk {
     pub warnings: Vec<String>,
 }
 
+impl ApiOk {
+    ///
+    /// Instant vector of an instant query result.
+    pub fn as_instant_vector(&self) -> Option<&[Instant]> {
+        match &self.data {
+            Some(Data::Expression(Expression::Instant(instants))) => Some(instants),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Range matrix of a range query result.
+    pub fn as_range_matrix(&self) -> Option<&[Range]> {
+        match &self.data {
+            Some(Data::Expression(Expression::Range(ranges))) => Some(ranges),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Scalar of a query result.
+    pub fn as_scalar(&self) -> Option<&Sample> {
+        match &self.data {
+            Some(Data::Expression(Expression::Scalar(sample))) => Some(sample),
+            _ => None,
+        }
+    }
+
+    ///
+    /// String of a query result.
+    pub fn as_string_sample(&self) -> Option<&StringSample> {
+        match &self.data {
+            Some(Data::Expression(Expression::String(sample))) => Some(sample),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Metrics of a series result.
+    ///
+    /// Empty label names or values are indistinguishable from empty series, they are
+    /// returned as empty series as well.
+    pub fn as_series(&self) -> Option<&[Metric]> {
+        match &self.data {
+            Some(Data::Series(Series(metrics))) => Some(metrics),
+            Some(Data::LabelsOrValues(LabelsOrValues(values))) if values.is_empty() => Some(&[]),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Label names or label values of a labels result.
+    ///
+    /// Empty series are indistinguishable from empty labels, they are returned as empty labels.
+    pub fn as_labels(&self) -> Option<&[String]> {
+        match &self.data {
+            Some(Data::LabelsOrValues(LabelsOrValues(values))) => Some(values),
+            Some(Data::Series(Series(metrics))) if metrics.is_empty() => Some(&[]),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Infer the step Prometheus used for a range query result.
+    ///
+    /// The step is the smallest spacing between consecutive samples of the first series,
+    /// rounded to milliseconds. Gaps of missing samples therefore don't affect it.
+    /// Returns `None` for non-matrix results or when the first series has less than two samples.
+    pub fn detected_step(&self) -> Option<Duration> {
+        let samples = match &self.data {
+            Some(Data::Expression(Expression::Range(ranges))) => &ranges.first()?.samples,
+            _ => return None,
+        };
+
+        samples
+            .windows(2)
+            .map(|w| ((w[1].epoch - w[0].epoch) * 1000.0).round())
+            .filter(|millis| *millis > 0.0)
+            .map(|millis| millis as u64)
+            .min()
+            .map(Duration::from_millis)
+    }
+
+    ///
+    /// Sorted union of the label names across the series of an instant vector result.
+    ///
+    /// Returns an empty set for other result types.
+    ///
+    /// # Arguments
+    ///
+    /// * `include_name` - whether the metric name label `__name__` is included
+    pub fn vector_label_keys(&self, include_name: bool) -> BTreeSet<String> {
+        let instants = match &self.data {
+            Some(Data::Expression(Expression::Instant(instants))) => instants,
+            _ => return BTreeSet::new(),
+        };
+
+        instants
+            .iter()
+            .flat_map(|instant| instant.metric.labels.keys())
+            .filter(|key| include_name || key.as_str() != PROQ_METRIC_NAME_LABEL)
+            .cloned()
+            .collect()
+    }
+
+    ///
+    /// Whether the result is degraded, e.g. partial due to an unavailable store.
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    ///
+    /// Categorize the warnings of the result. See [Warning::parse].
+    pub fn parsed_warnings(&self) -> Vec<Warning> {
+        self.warnings.iter().map(|w| Warning::parse(w)).collect()
+    }
+
+    ///
+    /// Group exemplars of the result into time buckets of the given width.
+    ///
+    /// Buckets are aligned to multiples of `bucket` since the unix epoch and identified
+    /// by their start timestamp in seconds. Only non-empty buckets are returned, sorted by
+    /// their start. Returns no buckets if the result has no exemplars or `bucket` is zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `bucket` - width of a time bucket
+    pub fn exemplars_in_window(&self, bucket: Duration) -> Vec<(f64, Vec<Exemplar>)> {
+        let width = bucket.as_secs_f64();
+        let series = match &self.data {
+            Some(Data::Exemplars(series)) if width > 0.0 => series,
+            _ => return Vec::new(),
+        };
+
+        let mut buckets: Vec<(f64, Vec<Exemplar>)> = Vec::new();
+        for exemplar in series.iter().flat_map(|s| s.exemplars.iter()) {
+            let start = (exemplar.timestamp / width).floor() * width;
+            match buckets.iter_mut().find(|(s, _)| *s == start) {
+                Some((_, exemplars)) => exemplars.push(exemplar.clone()),
+                None => buckets.push((start, vec![exemplar.clone()])),
+            }
+        }
+        buckets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        buckets
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct ApiErr {
     #[serde(rename = "errorType")]
@@ -52,18 +647,56 @@ pub struct ApiErr {
     pub warnings: Vec<String>,
 }
 
+impl From<ApiErr> for ProqError {
+    fn from(err: ApiErr) -> Self {
+        ProqError::ApiError {
+            error_type: err.error_type,
+            error_message: err.error_message,
+        }
+    }
+}
+
+impl fmt::Display for ApiErr {
+    ///
+    /// Render the error as `error_type: error_message`, followed by the warnings if there are any.
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}: {}", self.error_type, self.error_message)?;
+        if !self.warnings.is_empty() {
+            write!(f, " (warnings: {})", self.warnings.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApiErr {}
+
+impl ApiErr {
+    ///
+    /// Partial data returned along with the error, e.g. the samples evaluated before a timeout.
+    pub fn partial_data(&self) -> Option<&Data> {
+        self.data.as_ref()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum Data {
     Expression(Expression),
     Series(Series),
     LabelsOrValues(LabelsOrValues),
+    Exemplars(Vec<ExemplarSet>),
+    TargetMetadata(Vec<TargetMetadataEntry>),
     Targets(Targets),
     Rules(Rules),
     Alerts(Alerts),
     AlertManagers(AlertManagers),
     Config(Config),
     Snapshot(Snapshot),
+    TsdbStats(TsdbStats),
+    BuildInfo(BuildInfo),
+    WalReplay(WalReplay),
+    RuntimeInfo(RuntimeInfo),
+    Metadata(HashMap<String, Vec<MetricMetadata>>),
     // IMPORTANT: this must *always* be the final variant.
     // For untagged enums serde will attempt deserialization using
     // each variant in order and accept the first one that is successful.
@@ -100,12 +733,185 @@ pub struct Range {
     pub samples: Vec<Sample>,
 }
 
+impl Range {
+    ///
+    /// The p-th percentile (0 ≤ p ≤ 100) of the sample values.
+    ///
+    /// Values are linearly interpolated between the closest ranks, the same way as
+    /// PromQL's `quantile_over_time`. `NaN` samples are ignored.
+    ///
+    /// Returns `None` if `p` is out of range or there are no samples.
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - percentile to calculate
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if !(0.0..=100.0).contains(&p) {
+            return None;
+        }
+        let mut values = self.values();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let rank = p / 100.0 * (values.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        let weight = rank - lower as f64;
+        Some(values[lower] * (1.0 - weight) + values[upper] * weight)
+    }
+
+    ///
+    /// Arithmetic mean of the sample values, ignoring `NaN` samples.
+    pub fn mean(&self) -> Option<f64> {
+        let values = self.values();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    ///
+    /// Smallest sample value, ignoring `NaN` samples.
+    pub fn min(&self) -> Option<f64> {
+        self.values().into_iter().reduce(f64::min)
+    }
+
+    ///
+    /// Largest sample value, ignoring `NaN` samples.
+    pub fn max(&self) -> Option<f64> {
+        self.values().into_iter().reduce(f64::max)
+    }
+
+    ///
+    /// Population standard deviation of the sample values like PromQL's `stddev_over_time`,
+    /// ignoring `NaN` samples.
+    pub fn stddev(&self) -> Option<f64> {
+        let mean = self.mean()?;
+        let values = self.values();
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        Some(variance.sqrt())
+    }
+
+    fn values(&self) -> Vec<f64> {
+        self.samples
+            .iter()
+            .map(|s| s.value)
+            .filter(|v| !v.is_nan())
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Metric {
     #[serde(flatten)]
     pub labels: HashMap<String, String>,
 }
 
+impl Metric {
+    ///
+    /// Get a builder to construct a metric.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::prelude::*;
+    ///
+    /// let metric = Metric::builder().name("up").label("job", "prometheus").build();
+    /// assert_eq!(metric.to_string(), "up{job=\"prometheus\"}");
+    /// ```
+    pub fn builder() -> MetricBuilder {
+        MetricBuilder::default()
+    }
+
+    ///
+    /// Metric name, value of the `__name__` label.
+    pub fn name(&self) -> Option<&str> {
+        self.labels.get(PROQ_METRIC_NAME_LABEL).map(String::as_str)
+    }
+
+    ///
+    /// Add or replace a label.
+    pub fn with_label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(name.into(), value.into());
+        self
+    }
+
+    ///
+    /// Remove a label, returning its value if it was present.
+    pub fn remove_label(&mut self, name: &str) -> Option<String> {
+        self.labels.remove(name)
+    }
+}
+
+impl fmt::Display for Metric {
+    ///
+    /// Render the metric as series selector, e.g. `up{instance="localhost:9090",job="prometheus"}`.
+    ///
+    /// Labels are sorted by name.
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let mut labels: Vec<(&String, &String)> = self
+            .labels
+            .iter()
+            .filter(|(k, _)| k.as_str() != PROQ_METRIC_NAME_LABEL)
+            .collect();
+        labels.sort();
+
+        if let Some(name) = self.name() {
+            f.write_str(name)?;
+            if labels.is_empty() {
+                return Ok(());
+            }
+        }
+
+        f.write_str("{")?;
+        for (i, (k, v)) in labels.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            let escaped = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            write!(f, "{}=\"{}\"", k, escaped)?;
+        }
+        f.write_str("}")
+    }
+}
+
+///
+/// Builder of [Metric].
+#[derive(Clone, Debug, Default)]
+pub struct MetricBuilder {
+    labels: HashMap<String, String>,
+}
+
+impl MetricBuilder {
+    ///
+    /// Set the metric name.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.labels
+            .insert(PROQ_METRIC_NAME_LABEL.to_owned(), name.into());
+        self
+    }
+
+    ///
+    /// Add or replace a label.
+    pub fn label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(name.into(), value.into());
+        self
+    }
+
+    ///
+    /// Build the metric.
+    pub fn build(self) -> Metric {
+        Metric {
+            labels: self.labels,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Sample {
     pub epoch: f64,
@@ -138,9 +944,9 @@ impl<'de> Deserialize<'de> for Sample {
                     .ok_or_else(|| de::Error::missing_field("sample value"))?;
 
                 let value = match value {
-                    PROQ_INFINITY => std::f64::INFINITY,
-                    PROQ_NEGATIVE_INFINITY => std::f64::NEG_INFINITY,
-                    PROQ_NAN => std::f64::NAN,
+                    PROQ_INFINITY | PROQ_POSITIVE_INFINITY => f64::INFINITY,
+                    PROQ_NEGATIVE_INFINITY => f64::NEG_INFINITY,
+                    PROQ_NAN => f64::NAN,
                     _ => value
                         .parse::<f64>()
                         .map_err(|_| de::Error::invalid_value(Unexpected::Str(value), &self))?,
@@ -161,7 +967,7 @@ impl Serialize for Sample {
     {
         let mut s = serializer.serialize_tuple(2)?;
         s.serialize_element(&self.epoch)?;
-        s.serialize_element(&self.value)?;
+        s.serialize_element(&format_prometheus_f64(self.value))?;
         s.end()
     }
 }
@@ -220,9 +1026,63 @@ impl Serialize for StringSample {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Series(pub Vec<Metric>);
 
+impl Series {
+    ///
+    /// Sorted distinct values of every label key present across the metrics.
+    ///
+    /// Useful for building filters without querying label values of each key.
+    pub fn facets(&self) -> HashMap<String, Vec<String>> {
+        let mut facets: HashMap<String, BTreeSet<&str>> = HashMap::new();
+        for metric in &self.0 {
+            for (key, value) in &metric.labels {
+                facets.entry(key.clone()).or_default().insert(value);
+            }
+        }
+
+        facets
+            .into_iter()
+            .map(|(key, values)| (key, values.into_iter().map(ToOwned::to_owned).collect()))
+            .collect()
+    }
+}
+
+impl Index<usize> for Series {
+    type Output = Metric;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct LabelsOrValues(pub Vec<String>);
 
+impl Index<usize> for LabelsOrValues {
+    type Output = String;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExemplarSet {
+    pub series_labels: HashMap<String, String>,
+    pub exemplars: Vec<Exemplar>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Exemplar {
+    pub labels: HashMap<String, String>,
+    #[serde(
+        deserialize_with = "prometheus_string_to_f64",
+        serialize_with = "f64_to_prometheus_string"
+    )]
+    pub value: f64,
+    pub timestamp: f64,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Targets {
@@ -232,11 +1092,33 @@ pub struct Targets {
     pub dropped: Vec<DroppedTarget>,
 }
 
+impl Targets {
+    ///
+    /// Distinct scrape pool names of the active targets in sorted order.
+    ///
+    /// Targets without a scrape pool fall back to their `job` label.
+    pub fn scrape_pools(&self) -> Vec<String> {
+        self.active
+            .iter()
+            .filter_map(|t| {
+                t.scrape_pool
+                    .as_ref()
+                    .or_else(|| t.labels.get(PROQ_JOB_LABEL))
+                    .cloned()
+            })
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActiveTarget {
     pub discovered_labels: HashMap<String, String>,
     pub labels: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrape_pool: Option<String>,
     #[serde(with = "url_serde")]
     pub scrape_url: Url,
     #[serde(
@@ -256,6 +1138,73 @@ pub struct ActiveTarget {
     pub health: TargetHealth,
 }
 
+impl ActiveTarget {
+    ///
+    /// Compare discovered labels with the final labels of the target.
+    ///
+    /// Reports which labels are added, removed or changed by relabeling.
+    /// Changes are sorted by label name.
+    pub fn relabel_diff(&self) -> Vec<LabelChange> {
+        let mut changes: Vec<LabelChange> = self
+            .discovered_labels
+            .iter()
+            .filter_map(|(name, value)| match self.labels.get(name) {
+                None => Some(LabelChange::Removed {
+                    name: name.clone(),
+                    value: value.clone(),
+                }),
+                Some(to) if to != value => Some(LabelChange::Changed {
+                    name: name.clone(),
+                    from: value.clone(),
+                    to: to.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        changes.extend(
+            self.labels
+                .iter()
+                .filter(|(name, _)| !self.discovered_labels.contains_key(*name))
+                .map(|(name, value)| LabelChange::Added {
+                    name: name.clone(),
+                    value: value.clone(),
+                }),
+        );
+
+        changes.sort_by(|a, b| a.name().cmp(b.name()));
+        changes
+    }
+}
+
+///
+/// Single label difference between discovered and final target labels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LabelChange {
+    /// Label only exists after relabeling
+    Added { name: String, value: String },
+    /// Label is dropped by relabeling
+    Removed { name: String, value: String },
+    /// Label value is rewritten by relabeling
+    Changed {
+        name: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl LabelChange {
+    ///
+    /// Name of the label this change is about.
+    pub fn name(&self) -> &str {
+        match self {
+            LabelChange::Added { name, .. }
+            | LabelChange::Removed { name, .. }
+            | LabelChange::Changed { name, .. } => name,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum TargetHealth {
     Up,
@@ -263,6 +1212,32 @@ pub enum TargetHealth {
     Unknown,
 }
 
+fn prometheus_string_to_f64<'de, D: Deserializer<'de>>(d: D) -> StdResult<f64, D::Error> {
+    let s = String::deserialize(d)?;
+    match s.as_str() {
+        PROQ_INFINITY | PROQ_POSITIVE_INFINITY => Ok(f64::INFINITY),
+        PROQ_NEGATIVE_INFINITY => Ok(f64::NEG_INFINITY),
+        PROQ_NAN => Ok(f64::NAN),
+        _ => s.parse::<f64>().map_err(de::Error::custom),
+    }
+}
+
+fn f64_to_prometheus_string<S: Serializer>(v: &f64, serializer: S) -> StdResult<S::Ok, S::Error> {
+    serializer.serialize_str(&format_prometheus_f64(*v))
+}
+
+fn format_prometheus_f64(v: f64) -> String {
+    if v.is_nan() {
+        PROQ_NAN.to_owned()
+    } else if v.is_infinite() && v.is_sign_positive() {
+        PROQ_POSITIVE_INFINITY.to_owned()
+    } else if v.is_infinite() {
+        PROQ_NEGATIVE_INFINITY.to_owned()
+    } else {
+        v.to_string()
+    }
+}
+
 fn empty_string_is_none<'de, D: Deserializer<'de>>(d: D) -> StdResult<Option<String>, D::Error> {
     let o: Option<String> = Option::deserialize(d)?;
     Ok(o.filter(|s| !s.is_empty()))
@@ -273,7 +1248,7 @@ fn none_to_empty_string<S: Serializer>(
     serializer: S,
 ) -> StdResult<S::Ok, S::Error> {
     if let Some(v) = s {
-        serializer.serialize_str(&v)
+        serializer.serialize_str(v)
     } else {
         serializer.serialize_str("")
     }
@@ -348,7 +1323,7 @@ impl<'de> Deserialize<'de> for AlertManager {
         #[serde(field_identifier, rename_all = "lowercase")]
         enum Field {
             Url,
-        };
+        }
 
         const FIELDS: &[&str] = &["url"];
 
@@ -379,7 +1354,7 @@ impl<'de> Deserialize<'de> for AlertManager {
             }
         }
 
-        deserializer.deserialize_struct("AlertManager", &FIELDS, VisitorImpl)
+        deserializer.deserialize_struct("AlertManager", FIELDS, VisitorImpl)
     }
 }
 
@@ -399,11 +1374,225 @@ pub struct Snapshot {
     pub name: String,
 }
 
+///
+/// Loaded configuration of Prometheus.
+///
+/// Fields besides the YAML, like a hash sent by recent versions, are ignored.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Config {
     pub yaml: String,
 }
 
+impl Config {
+    ///
+    /// Hex encoded SHA-256 hash of the YAML, stable across calls and versions.
+    ///
+    /// Comparing hashes of polled configs is a cheap way to detect configuration changes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use proq::result_types::Config;
+    ///
+    /// let config = Config { yaml: "global: {}".to_owned() };
+    ///
+    /// assert_eq!(config.sha256().len(), 64);
+    /// assert_eq!(config.sha256(), config.clone().sha256());
+    /// ```
+    pub fn sha256(&self) -> String {
+        Sha256::digest(self.yaml.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TsdbStats {
+    pub head_stats: HeadStats,
+    #[serde(default)]
+    pub series_count_by_metric_name: Vec<TsdbStat>,
+    #[serde(default)]
+    pub label_value_count_by_label_name: Vec<TsdbStat>,
+    #[serde(default)]
+    pub memory_in_bytes_by_label_name: Vec<TsdbStat>,
+    #[serde(default)]
+    pub series_count_by_label_value_pair: Vec<TsdbStat>,
+}
+
+impl TsdbStats {
+    ///
+    /// Lowest sample timestamp in the head block.
+    pub fn head_min_time(&self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_millis_opt(self.head_stats.min_time).single()
+    }
+
+    ///
+    /// Highest sample timestamp in the head block.
+    pub fn head_max_time(&self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_millis_opt(self.head_stats.max_time).single()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeInfo {
+    #[serde(
+        deserialize_with = "rfc3339_to_date_time",
+        serialize_with = "date_time_to_rfc3339"
+    )]
+    pub start_time: DateTime<FixedOffset>,
+    #[serde(rename = "CWD")]
+    pub cwd: String,
+    pub reload_config_success: bool,
+    #[serde(
+        deserialize_with = "rfc3339_to_date_time",
+        serialize_with = "date_time_to_rfc3339"
+    )]
+    pub last_config_time: DateTime<FixedOffset>,
+    /// Not reported anymore by recent Prometheus versions.
+    #[serde(default)]
+    pub chunk_count: i64,
+    pub time_series_count: i64,
+    pub corruption_count: i64,
+    pub goroutine_count: i64,
+    #[serde(rename = "GOMAXPROCS")]
+    pub gomaxprocs: i64,
+    pub storage_retention: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WalReplay {
+    pub min: i64,
+    pub max: i64,
+    pub current: i64,
+    /// Not reported by Prometheus itself, only by some compatible backends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<WalReplayState>,
+}
+
+impl WalReplay {
+    ///
+    /// Replay progress in percent, computed over the segment range like the Prometheus UI does.
+    ///
+    /// Without reported state the replay is done once the current segment reaches the last one.
+    pub fn progress_percent(&self) -> f64 {
+        let done = match self.state {
+            Some(ref state) => *state == WalReplayState::DONE,
+            None => self.current >= self.max,
+        };
+        if done || self.max < self.min {
+            return 100.0;
+        }
+        let replayed = (self.current - self.min + 1).max(0) as f64;
+        let total = (self.max - self.min + 1) as f64;
+        (replayed / total * 100.0).min(100.0)
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum WalReplayState {
+    WAITING,
+    #[serde(rename = "in progress")]
+    INPROGRESS,
+    DONE,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MetricMetadata {
+    #[serde(rename = "type")]
+    pub metric_type: String,
+    pub help: String,
+    pub unit: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TargetMetadataEntry {
+    /// Labels of the target exposing the metric
+    pub target: HashMap<String, String>,
+    /// Metric name, omitted when the metadata is requested for a single metric
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<String>,
+    #[serde(rename = "type")]
+    pub metric_type: String,
+    pub help: String,
+    pub unit: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    /// Application name, only reported by some compatible backends (e.g. Grafana Mimir).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub application: Option<String>,
+    pub version: String,
+    pub revision: String,
+    pub branch: String,
+    #[serde(default)]
+    pub build_user: String,
+    #[serde(default)]
+    pub build_date: String,
+    pub go_version: String,
+}
+
+impl BuildInfo {
+    ///
+    /// Heuristically classify the server which reported this build information.
+    ///
+    /// Compatible backends identify themselves in the application name, version, branch
+    /// or build user. Anything without such a hint is considered to be Prometheus.
+    pub fn backend_kind(&self) -> BackendKind {
+        let hints = [
+            self.application.as_deref().unwrap_or_default(),
+            self.version.as_str(),
+            self.branch.as_str(),
+            self.build_user.as_str(),
+        ]
+        .join(" ")
+        .to_lowercase();
+
+        if hints.contains("victoria") {
+            BackendKind::VictoriaMetrics
+        } else if hints.contains("thanos") {
+            BackendKind::Thanos
+        } else if hints.contains("cortex") || hints.contains("mimir") {
+            BackendKind::Cortex
+        } else {
+            BackendKind::Prometheus
+        }
+    }
+}
+
+///
+/// Kind of server behind the Prometheus HTTP API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BackendKind {
+    Prometheus,
+    Thanos,
+    /// Cortex or Grafana Mimir.
+    Cortex,
+    VictoriaMetrics,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadStats {
+    pub num_series: u64,
+    pub chunk_count: u64,
+    /// Unix timestamp in milliseconds
+    pub min_time: i64,
+    /// Unix timestamp in milliseconds
+    pub max_time: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TsdbStat {
+    pub name: String,
+    pub value: u64,
+}
+
 #[derive(PartialEq, Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum AlertState {
@@ -418,6 +1607,46 @@ pub struct Rules {
     pub groups: Vec<RuleGroups>,
 }
 
+impl Rules {
+    ///
+    /// Distinct rule group names in sorted order.
+    pub fn group_names(&self) -> Vec<&str> {
+        self.groups
+            .iter()
+            .map(|g| g.name.as_str())
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .collect()
+    }
+
+    ///
+    /// Distinct rule files in sorted order.
+    pub fn files(&self) -> Vec<&str> {
+        self.groups
+            .iter()
+            .map(|g| g.file.as_str())
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .collect()
+    }
+
+    ///
+    /// Rules of all groups in a flat list, in group order.
+    pub fn all_rules(&self) -> Vec<&Rule> {
+        self.groups.iter().flat_map(|g| g.rules.iter()).collect()
+    }
+
+    ///
+    /// Rules of all groups loaded from the given rule file, in group order.
+    pub fn rules_in_file(&self, file: &str) -> Vec<&Rule> {
+        self.groups
+            .iter()
+            .filter(|g| g.file == file)
+            .flat_map(|g| g.rules.iter())
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct RuleGroups {
     pub rules: Vec<Rule>,
@@ -446,6 +1675,19 @@ pub struct Rule {
     pub rule_type: RuleType,
 }
 
+impl Rule {
+    ///
+    /// Name of the metric a recording rule writes its result to.
+    ///
+    /// Alerting rules don't produce a metric of their own and return `None`.
+    pub fn output_metric(&self) -> Option<&str> {
+        match self.rule_type {
+            RuleType::RECORDING => Some(self.name.as_str()),
+            RuleType::ALERTING => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Alert {
     #[serde(default, rename = "activeAt")]
@@ -454,6 +1696,37 @@ pub struct Alert {
     pub labels: Option<HashMap<String, String>>,
     pub state: AlertState,
     pub value: String,
+    #[serde(
+        default,
+        rename = "keepFiringSince",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub keep_firing_since: Option<String>,
+}
+
+impl Alert {
+    ///
+    /// Parsed `activeAt` timestamp of the alert.
+    pub fn active_at_time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.active_at).ok()
+    }
+
+    ///
+    /// Parsed `keepFiringSince` timestamp of the alert.
+    pub fn keep_firing_since_time(&self) -> Option<DateTime<FixedOffset>> {
+        self.keep_firing_since
+            .as_ref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+    }
+
+    ///
+    /// How long the alert has been active at the given time.
+    ///
+    /// Returns `None` if `activeAt` can't be parsed or it is after `now`.
+    pub fn firing_duration(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.active_at_time()
+            .and_then(|active_at| now.signed_duration_since(active_at).to_std().ok())
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -0,0 +1,258 @@
+//!
+//! Client-side checks for series selectors.
+//!
+//! Catches common mistakes like unbalanced braces or unquoted label values
+//! before the request is sent to Prometheus.
+
+use crate::errors::*;
+use crate::result_types::Metric;
+
+///
+/// Client-side filter of metrics by their labels.
+///
+/// All matchers must match. Missing labels have the empty value, like in PromQL.
+///
+/// # Example
+///
+/// ```rust
+/// use proq::prelude::*;
+///
+/// let metric = Metric::builder().name("up").label("job", "node").build();
+///
+/// assert!(LabelSelector::new().equal("job", "node").matches(&metric));
+/// assert!(!LabelSelector::new().not_equal("job", "node").matches(&metric));
+/// assert!(LabelSelector::new().equal("instance", "").matches(&metric));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LabelSelector {
+    matchers: Vec<(String, LabelMatch)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum LabelMatch {
+    Equal(String),
+    NotEqual(String),
+    OneOf(Vec<String>),
+}
+
+impl LabelSelector {
+    ///
+    /// Selector matching every metric.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Match metrics with the label `name` equal to `value`.
+    pub fn equal(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.matchers
+            .push((name.into(), LabelMatch::Equal(value.into())));
+        self
+    }
+
+    ///
+    /// Match metrics with the label `name` not equal to `value`.
+    pub fn not_equal(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.matchers
+            .push((name.into(), LabelMatch::NotEqual(value.into())));
+        self
+    }
+
+    ///
+    /// Match metrics with the label `name` equal to any of the `values`.
+    pub fn one_of<V: Into<String>>(
+        mut self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.matchers.push((name.into(), LabelMatch::OneOf(values)));
+        self
+    }
+
+    ///
+    /// Whether the labels of the metric satisfy all matchers.
+    pub fn matches(&self, metric: &Metric) -> bool {
+        self.matchers.iter().all(|(name, matcher)| {
+            let value = metric.labels.get(name).map_or("", String::as_str);
+            match matcher {
+                LabelMatch::Equal(expected) => value == expected,
+                LabelMatch::NotEqual(unexpected) => value != unexpected,
+                LabelMatch::OneOf(values) => values.iter().any(|v| v == value),
+            }
+        })
+    }
+}
+
+///
+/// Validate the syntax of a series selector like `http_requests_total{job="api", code=~"5.."}`.
+///
+/// Checks metric and label names, balanced braces, quoted label values and matching operators
+/// (`=`, `!=`, `=~`, `!~`). Errors are returned as [ProqError::InvalidSelector] with the byte
+/// position of the problem.
+///
+/// # Arguments
+///
+/// * `selector` - series selector to validate
+///
+/// # Example
+///
+/// ```rust
+/// use proq::prelude::*;
+///
+/// assert!(validate_selector("up{job=\"prometheus\"}").is_ok());
+/// assert!(validate_selector("up{job=\"prometheus\"").is_err());
+/// ```
+pub fn validate_selector(selector: &str) -> ProqResult<()> {
+    SelectorParser {
+        input: selector,
+        pos: 0,
+    }
+    .parse()
+}
+
+struct SelectorParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn parse(mut self) -> ProqResult<()> {
+        self.skip_whitespace();
+        let has_name = self.peek().is_some_and(is_metric_name_start);
+        if has_name {
+            self.take_while(is_metric_name_char);
+            self.skip_whitespace();
+        }
+
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                self.parse_matchers()?;
+            }
+            None if has_name => return Ok(()),
+            None => return Err(self.error("expected metric name or '{'")),
+            Some(_) if !has_name => return Err(self.error("expected metric name or '{'")),
+            Some(_) => {}
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("unexpected character after selector")),
+        }
+    }
+
+    fn parse_matchers(&mut self) -> ProqResult<()> {
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(());
+                }
+                None => return Err(self.error("unclosed '{'")),
+                Some(c) if is_label_name_start(c) => {
+                    self.take_while(is_label_name_char);
+                }
+                Some(_) => return Err(self.error("expected label name")),
+            }
+
+            self.skip_whitespace();
+            self.parse_operator()?;
+            self.skip_whitespace();
+            self.parse_string()?;
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {}
+                None => return Err(self.error("unclosed '{'")),
+                Some(_) => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_operator(&mut self) -> ProqResult<()> {
+        let rest = &self.input[self.pos..];
+        let len = ["=~", "!~", "!=", "="]
+            .iter()
+            .find(|op| rest.starts_with(*op))
+            .map(|op| op.len())
+            .ok_or_else(|| self.error("expected one of '=', '!=', '=~', '!~'"))?;
+        self.pos += len;
+        Ok(())
+    }
+
+    fn parse_string(&mut self) -> ProqResult<()> {
+        let quote = match self.peek() {
+            Some(q) if q == '"' || q == '\'' || q == '`' => q,
+            _ => return Err(self.error("expected quoted label value")),
+        };
+        let start = self.pos;
+        self.bump();
+
+        while let Some(c) = self.bump() {
+            match c {
+                c if c == quote => return Ok(()),
+                '\\' if quote != '`' => {
+                    self.bump();
+                }
+                _ => {}
+            }
+        }
+
+        self.pos = start;
+        Err(self.error("unterminated label value"))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn error(&self, message: &str) -> ProqError {
+        ProqError::InvalidSelector {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+}
+
+pub(crate) fn is_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_label_name_start) && chars.all(is_label_name_char)
+}
+
+fn is_metric_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == ':'
+}
+
+fn is_metric_name_char(c: char) -> bool {
+    is_metric_name_start(c) || c.is_ascii_digit()
+}
+
+fn is_label_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_label_name_char(c: char) -> bool {
+    is_label_name_start(c) || c.is_ascii_digit()
+}
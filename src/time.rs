@@ -0,0 +1,71 @@
+//!
+//! Relative time expressions like `now-1h`, as used in Grafana time ranges.
+//!
+//! Parsed times feed directly into queries, e.g. [crate::api::ProqClient::range_query].
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::errors::*;
+
+const PROQ_NOW: &str = "now";
+
+///
+/// Parse a relative time expression into an absolute time.
+///
+/// Expressions are `now` optionally followed by `-` or `+` and an offset like `30m`.
+/// Offset units are `s`, `m`, `h`, `d`, `w` and `y` (365 days).
+/// Invalid expressions are reported as [ProqError::InvalidParameter].
+///
+/// # Arguments
+///
+/// * `expr` - relative time expression
+/// * `now` - reference time of `now`
+///
+/// # Example
+///
+/// ```rust
+/// use proq::time::parse_relative_time;
+/// use chrono::{Duration, Utc};
+///
+/// let now = Utc::now();
+///
+/// assert_eq!(parse_relative_time("now-1h", now).unwrap(), now - Duration::hours(1));
+/// assert!(parse_relative_time("yesterday", now).is_err());
+/// ```
+pub fn parse_relative_time(expr: &str, now: DateTime<Utc>) -> ProqResult<DateTime<Utc>> {
+    let invalid = || ProqError::InvalidParameter(format!("invalid relative time: {}", expr));
+
+    let offset = expr
+        .trim()
+        .strip_prefix(PROQ_NOW)
+        .ok_or_else(invalid)?
+        .trim_start();
+    if offset.is_empty() {
+        return Ok(now);
+    }
+
+    let (sign, offset) = if let Some(offset) = offset.strip_prefix('-') {
+        (-1, offset.trim_start())
+    } else if let Some(offset) = offset.strip_prefix('+') {
+        (1, offset.trim_start())
+    } else {
+        return Err(invalid());
+    };
+    let unit_start = offset
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let amount: i32 = offset[..unit_start].parse().map_err(|_| invalid())?;
+    let unit = match &offset[unit_start..] {
+        "s" => Duration::seconds(1),
+        "m" => Duration::minutes(1),
+        "h" => Duration::hours(1),
+        "d" => Duration::days(1),
+        "w" => Duration::weeks(1),
+        "y" => Duration::days(365),
+        _ => return Err(invalid()),
+    };
+
+    unit.checked_mul(amount * sign)
+        .and_then(|offset| now.checked_add_signed(offset))
+        .ok_or_else(invalid)
+}
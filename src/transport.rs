@@ -0,0 +1,641 @@
+//!
+//! Transport level helpers which are applied to the underlying HTTP client.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures::future::{self, Either};
+use futures_preview::io::AsyncReadExt;
+use futures_timer::Delay;
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use http::StatusCode;
+use isahc::config::{CaCertificate, ClientCertificate, Configurable, PrivateKey, SslOption};
+use serde::Deserialize;
+use surf::middleware::{Body, HttpClient, Middleware, Next, Request, Response};
+
+use crate::errors::*;
+use crate::result_types::{ApiOk, ApiResult, RawApiResult, ResponseMeta};
+use crate::value_types::prometheus_types::PROQ_ERROR_TYPE_UNAVAILABLE;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+///
+/// TLS settings of the HTTP client.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TlsConfig {
+    /// Accept any server certificate, see [crate::api::ProqClientBuilder::insecure_skip_tls_verify]
+    pub(crate) insecure_skip_verify: bool,
+    /// PEM bundle of trusted CA certificates, see [crate::api::ProqClientBuilder::ca_bundle]
+    pub(crate) ca_bundle: Option<PathBuf>,
+    /// PEM files of the client certificate and its private key, see
+    /// [crate::api::ProqClientBuilder::client_certificate]
+    pub(crate) client_certificate: Option<(PathBuf, PathBuf)>,
+}
+
+///
+/// HTTP client sending the requests.
+///
+/// Curl based client like the default one of surf, but configurable and shared by
+/// all requests of a [crate::api::ProqClient].
+#[derive(Debug, Clone)]
+pub(crate) struct IsahcClient(Arc<isahc::HttpClient>);
+
+impl IsahcClient {
+    pub(crate) fn new(tls: &TlsConfig) -> ProqResult<Self> {
+        let mut builder = isahc::HttpClient::builder();
+        if tls.insecure_skip_verify {
+            builder = builder.ssl_options(
+                SslOption::DANGER_ACCEPT_INVALID_CERTS | SslOption::DANGER_ACCEPT_INVALID_HOSTS,
+            );
+        }
+        if let Some(path) = &tls.ca_bundle {
+            validate_pem_file(path, "CERTIFICATE")?;
+            builder = builder.ssl_ca_certificate(CaCertificate::file(path));
+        }
+        if let Some((cert, key)) = &tls.client_certificate {
+            validate_pem_file(cert, "CERTIFICATE")?;
+            validate_pem_file(key, "PRIVATE KEY")?;
+            builder = builder.ssl_client_certificate(ClientCertificate::pem_file(
+                cert,
+                PrivateKey::pem_file(key, None),
+            ));
+        }
+        let client = builder
+            .build()
+            .map_err(|e| ProqError::HTTPClientError(Box::new(e)))?;
+        Ok(IsahcClient(Arc::new(client)))
+    }
+}
+
+impl HttpClient for IsahcClient {
+    type Error = isahc::Error;
+
+    fn send(&self, req: Request) -> BoxFuture<'static, Result<Response, Self::Error>> {
+        let client = self.0.clone();
+        Box::pin(async move {
+            // Request bodies are small forms, they are sent from memory.
+            let (parts, mut body) = req.into_parts();
+            let mut bytes = Vec::new();
+            body.read_to_end(&mut bytes).await?;
+
+            let mut builder = isahc::http::Request::builder()
+                .method(parts.method.as_str())
+                .uri(parts.uri.to_string());
+            for (name, value) in parts.headers.iter() {
+                builder = builder.header(name.as_str(), value.as_bytes());
+            }
+            let req = builder.body(isahc::AsyncBody::from(bytes))?;
+
+            let (parts, body) = client.send_async(req).await?.into_parts();
+            let mut response = http::Response::new(Body::from_reader(ResponseBody(body)));
+            *response.status_mut() =
+                StatusCode::from_u16(parts.status.as_u16()).map_err(invalid_data)?;
+            for (name, value) in parts.headers.iter() {
+                response.headers_mut().append(
+                    HeaderName::from_bytes(name.as_str().as_bytes()).map_err(invalid_data)?,
+                    HeaderValue::from_bytes(value.as_bytes()).map_err(invalid_data)?,
+                );
+            }
+            Ok(response)
+        })
+    }
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> isahc::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e).into()
+}
+
+///
+/// Response body of isahc, readable by surf.
+struct ResponseBody(isahc::AsyncBody);
+
+impl futures_preview::io::AsyncRead for ResponseBody {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        futures::io::AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+    }
+}
+
+///
+/// Check that the PEM file holds at least one block of the `kind`, e.g. `CERTIFICATE` or
+/// `PRIVATE KEY`, and nothing unparseable.
+///
+/// Blocks must decode to a single DER encoded structure, their content isn't inspected further.
+fn validate_pem_file(path: &Path, kind: &str) -> ProqResult<()> {
+    let invalid =
+        |message: String| ProqError::InvalidCertificate(format!("{}: {}", path.display(), message));
+    let pem = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    validate_pem(&pem, kind).map_err(invalid)
+}
+
+fn validate_pem(pem: &str, kind: &str) -> Result<(), String> {
+    const BEGIN: &str = "-----BEGIN ";
+    const DASHES: &str = "-----";
+
+    let name = kind.to_lowercase();
+    let mut blocks = 0;
+    let mut rest = pem;
+    while let Some(start) = rest.find(BEGIN) {
+        let block = &rest[start + BEGIN.len()..];
+        let label_end = block
+            .find(DASHES)
+            .ok_or_else(|| "PEM header is not terminated".to_owned())?;
+        let label = &block[..label_end];
+        let block = &block[label_end + DASHES.len()..];
+        let end_marker = format!("-----END {}-----", label);
+        let end = block
+            .find(&end_marker)
+            .ok_or_else(|| format!("PEM block {} is not terminated", label))?;
+
+        if label.ends_with(kind) {
+            blocks += 1;
+            // No passphrase can be given, PKCS#8 and legacy `Proc-Type: 4,ENCRYPTED` keys
+            // would only fail during the handshake.
+            if label.starts_with("ENCRYPTED") || block[..end].contains("ENCRYPTED") {
+                return Err(format!("{} {} is encrypted", name, blocks));
+            }
+            let base64: String = block[..end].split_whitespace().collect();
+            let der = STANDARD
+                .decode(&base64)
+                .map_err(|_| format!("{} {} is not valid base64", name, blocks))?;
+            if !is_der_sequence(&der) {
+                return Err(format!(
+                    "{} {} is not a DER encoded structure",
+                    name, blocks
+                ));
+            }
+        }
+        rest = &block[end + end_marker.len()..];
+    }
+
+    if blocks == 0 {
+        return Err(format!("no PEM {} found", name));
+    }
+    Ok(())
+}
+
+///
+/// Middleware that writes prepared request headers to the outgoing request.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestHeaders(HeaderMap);
+
+impl TryFrom<&HashMap<String, String>> for RequestHeaders {
+    type Error = ProqError;
+
+    fn try_from(headers: &HashMap<String, String>) -> ProqResult<Self> {
+        let mut map = HeaderMap::with_capacity(headers.len());
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| ProqError::InvalidParameter(format!("header name {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                ProqError::InvalidParameter(format!("header value {}: {}", name, e))
+            })?;
+            map.insert(name, value);
+        }
+        Ok(RequestHeaders(map))
+    }
+}
+
+impl<C: HttpClient> Middleware<C> for RequestHeaders {
+    fn handle<'a>(
+        &'a self,
+        mut req: Request,
+        client: C,
+        next: Next<'a, C>,
+    ) -> BoxFuture<'a, Result<Response, surf::Exception>> {
+        for (name, value) in self.0.iter() {
+            req.headers_mut().insert(name.clone(), value.clone());
+        }
+        next.run(req, client)
+    }
+}
+
+///
+/// `Authorization` header value of the HTTP Basic authentication scheme, see RFC 7617.
+pub(crate) fn basic_auth_header(username: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", username, password))
+    )
+}
+
+///
+/// Whether the bytes are exactly one DER `SEQUENCE`, the outer structure of certificates and keys.
+fn is_der_sequence(der: &[u8]) -> bool {
+    let (length, content) = match der {
+        [0x30, length, content @ ..] if length & 0x80 == 0 => (usize::from(*length), content),
+        [0x30, length, rest @ ..] => {
+            let octets = usize::from(length & 0x7f);
+            if octets == 0 || octets > 4 || rest.len() < octets {
+                return false;
+            }
+            let (length, content) = rest.split_at(octets);
+            let length = length
+                .iter()
+                .fold(0_usize, |length, byte| length << 8 | usize::from(*byte));
+            (length, content)
+        }
+        _ => return false,
+    };
+    content.len() == length
+}
+
+///
+/// Fail with [ProqError::Timeout] if the request doesn't complete within `timeout`.
+pub(crate) async fn with_deadline<T>(
+    request: impl Future<Output = ProqResult<T>>,
+    timeout: Option<Duration>,
+) -> ProqResult<T> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return request.await,
+    };
+
+    futures::pin_mut!(request);
+    match future::select(request, Delay::new(timeout)).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(ProqError::Timeout { timeout }),
+    }
+}
+
+///
+/// Read the whole response body, aborting when it exceeds `limit` bytes.
+pub(crate) async fn read_body(
+    res: &mut surf::Response,
+    limit: Option<usize>,
+) -> ProqResult<Vec<u8>> {
+    let content_length = res
+        .header(header::CONTENT_LENGTH.as_str())
+        .and_then(|l| l.parse::<usize>().ok());
+    if let (Some(limit), Some(length)) = (limit, content_length) {
+        if length > limit {
+            return Err(ProqError::ResponseTooLarge { limit });
+        }
+    }
+    // Content-Length is the encoded size when the body is transparently decoded.
+    let expected_length = content_length.filter(|_| {
+        res.header(header::CONTENT_ENCODING.as_str())
+            .is_none_or(|e| e.eq_ignore_ascii_case("identity"))
+    });
+
+    let mut body = Vec::with_capacity(expected_length.unwrap_or_default());
+    let read = res
+        .take(limit.map_or(u64::MAX, |limit| limit as u64 + 1))
+        .read_to_end(&mut body)
+        .await;
+    if let Err(e) = read {
+        return Err(ProqError::Incomplete {
+            received_bytes: body.len(),
+            message: e.to_string(),
+        });
+    }
+    if let Some(limit) = limit.filter(|limit| body.len() > *limit) {
+        return Err(ProqError::ResponseTooLarge { limit });
+    }
+    if let Some(expected) = expected_length.filter(|expected| body.len() < *expected) {
+        return Err(ProqError::Incomplete {
+            received_bytes: body.len(),
+            message: format!("expected {} bytes", expected),
+        });
+    }
+
+    Ok(body)
+}
+
+///
+/// Check that the body is valid UTF-8, invalid sequences are replaced with `U+FFFD` if `lossy`.
+pub(crate) fn utf8_body(body: Vec<u8>, lossy: bool) -> ProqResult<Vec<u8>> {
+    match String::from_utf8(body) {
+        Ok(body) => Ok(body.into_bytes()),
+        Err(e) if lossy => Ok(String::from_utf8_lossy(e.as_bytes())
+            .into_owned()
+            .into_bytes()),
+        Err(e) => Err(ProqError::InvalidEncoding {
+            valid_up_to: e.utf8_error().valid_up_to(),
+        }),
+    }
+}
+
+///
+/// Collect transport level details of a response with the already decoded `body`.
+///
+/// The encoded size is only known when the response is encoded and carries `Content-Length`,
+/// since the HTTP client transparently decodes compressed bodies.
+pub(crate) fn response_meta(res: &mut surf::Response, body: &[u8]) -> ResponseMeta {
+    let content_encoding = res
+        .header(header::CONTENT_ENCODING.as_str())
+        .filter(|e| !e.eq_ignore_ascii_case("identity"))
+        .map(ToOwned::to_owned);
+    let compressed_bytes = content_encoding.as_ref().and_then(|_| {
+        res.header(header::CONTENT_LENGTH.as_str())
+            .and_then(|l| l.parse::<usize>().ok())
+    });
+
+    ResponseMeta {
+        content_encoding,
+        compressed_bytes,
+        decompressed_bytes: body.len(),
+        query_id: None,
+        headers: response_headers(res),
+    }
+}
+
+fn response_headers(res: &mut surf::Response) -> HashMap<String, String> {
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in res.headers() {
+        headers
+            .entry(name.to_ascii_lowercase())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(value);
+            })
+            .or_insert_with(|| value.to_owned());
+    }
+    headers
+}
+
+const PROQ_DATA_KEY: &[u8] = b"data";
+
+#[derive(Debug, PartialEq)]
+enum ScanPhase {
+    /// Looking for the `data` key of the top-level object.
+    SeekData,
+    /// Saw the `data` key, expecting the array to start.
+    DataValue,
+    /// Inside the `data` array, between elements.
+    Elements,
+    /// Inside an element of the `data` array.
+    Element,
+    /// `data` array is complete, the rest of the body is ignored.
+    Done,
+}
+
+///
+/// Incremental scanner yielding the raw elements of the top-level `data` array of a response.
+///
+/// Only the element being parsed is buffered, which keeps memory low for huge responses.
+#[derive(Debug)]
+pub(crate) struct DataArrayScanner {
+    phase: ScanPhase,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    last_string: Vec<u8>,
+    element: Vec<u8>,
+    ready: VecDeque<Vec<u8>>,
+    received_bytes: usize,
+}
+
+impl DataArrayScanner {
+    pub(crate) fn new() -> Self {
+        DataArrayScanner {
+            phase: ScanPhase::SeekData,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            last_string: Vec::new(),
+            element: Vec::new(),
+            ready: VecDeque::new(),
+            received_bytes: 0,
+        }
+    }
+
+    ///
+    /// Number of body bytes fed to the scanner so far.
+    pub(crate) fn received_bytes(&self) -> usize {
+        self.received_bytes
+    }
+
+    ///
+    /// Whether the whole `data` array is scanned.
+    pub(crate) fn is_done(&self) -> bool {
+        self.phase == ScanPhase::Done
+    }
+
+    ///
+    /// Next complete element of the `data` array.
+    pub(crate) fn next_element(&mut self) -> Option<Vec<u8>> {
+        self.ready.pop_front()
+    }
+
+    ///
+    /// Scan the next chunk of the body.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> ProqResult<()> {
+        self.received_bytes += chunk.len();
+        for &byte in chunk {
+            match self.phase {
+                ScanPhase::SeekData => self.seek_data(byte),
+                ScanPhase::DataValue => match byte {
+                    b'[' => self.phase = ScanPhase::Elements,
+                    b if b.is_ascii_whitespace() => {}
+                    b => {
+                        return Err(ProqError::UnexpectedData(format!(
+                            "expected data array, got: {}",
+                            b as char
+                        )))
+                    }
+                },
+                ScanPhase::Elements => match byte {
+                    b'{' | b'[' => {
+                        self.element.push(byte);
+                        self.depth = 1;
+                        self.phase = ScanPhase::Element;
+                    }
+                    b']' => self.phase = ScanPhase::Done,
+                    b',' => {}
+                    b if b.is_ascii_whitespace() => {}
+                    b => {
+                        return Err(ProqError::UnexpectedData(format!(
+                            "expected data array element, got: {}",
+                            b as char
+                        )))
+                    }
+                },
+                ScanPhase::Element => {
+                    self.element.push(byte);
+                    if self.scan_structure(byte) && self.depth == 0 {
+                        self.ready.push_back(std::mem::take(&mut self.element));
+                        self.phase = ScanPhase::Elements;
+                    }
+                }
+                ScanPhase::Done => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn seek_data(&mut self, byte: u8) {
+        let string_before = self.in_string;
+        let structural = self.scan_structure(byte);
+        if string_before && self.depth == 1 {
+            if self.in_string {
+                self.last_string.push(byte);
+            }
+        } else if structural && byte == b'"' && self.depth == 1 {
+            self.last_string.clear();
+        } else if structural && byte == b':' && self.depth == 1 && self.last_string == PROQ_DATA_KEY
+        {
+            self.phase = ScanPhase::DataValue;
+        }
+    }
+
+    ///
+    /// Track strings and nesting, returns whether the byte is outside of a string.
+    fn scan_structure(&mut self, byte: u8) -> bool {
+        if self.in_string {
+            if self.escaped {
+                self.escaped = false;
+            } else if byte == b'\\' {
+                self.escaped = true;
+            } else if byte == b'"' {
+                self.in_string = false;
+            }
+            return false;
+        }
+
+        match byte {
+            b'"' => self.in_string = true,
+            b'{' | b'[' => self.depth += 1,
+            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        true
+    }
+}
+
+#[cfg(not(feature = "simd-json"))]
+fn decode_json(body: &[u8]) -> ProqResult<ApiResult> {
+    serde_json::from_slice(body).map_err(|e| decode_error(body, e.to_string()))
+}
+
+#[cfg(feature = "simd-json")]
+fn decode_json(body: &[u8]) -> ProqResult<ApiResult> {
+    // simd-json parses in place.
+    let mut buf = body.to_vec();
+    simd_json::serde::from_slice(&mut buf).map_err(|e| decode_error(body, e.to_string()))
+}
+
+///
+/// Classify a decoding failure, bodies ending inside a string or an unclosed object or array
+/// are truncated rather than malformed.
+fn decode_error(body: &[u8], message: String) -> ProqError {
+    let mut scanner = DataArrayScanner::new();
+    for &byte in body {
+        scanner.scan_structure(byte);
+    }
+
+    if scanner.depth > 0 || scanner.in_string {
+        ProqError::Incomplete {
+            received_bytes: body.len(),
+            message,
+        }
+    } else {
+        ProqError::GenericError(message)
+    }
+}
+
+///
+/// Decode the response body as [ApiResult].
+///
+/// Successful responses without body (e.g. `204 No Content` of admin endpoints) are
+/// treated as success without data. Non-success responses are errors even with a Prometheus
+/// error body, see [status_error].
+pub(crate) fn recv_maybe_json(status: StatusCode, body: &[u8]) -> ProqResult<ApiResult> {
+    if !status.is_success() {
+        return Err(status_error(status, body));
+    }
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(ApiResult::ApiOk(ApiOk {
+            data: None,
+            warnings: Vec::new(),
+        }));
+    }
+
+    decode_json(body)
+}
+
+///
+/// Classify the response body by its status, without decoding the data.
+///
+/// Non-success responses are errors like in [recv_maybe_json], even with a Prometheus
+/// error body.
+pub(crate) fn recv_raw(status: StatusCode, body: Vec<u8>) -> ProqResult<RawApiResult> {
+    #[derive(Deserialize)]
+    struct Status<'a> {
+        #[serde(borrow)]
+        status: &'a str,
+    }
+
+    if !status.is_success() {
+        return Err(status_error(status, &body));
+    }
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(RawApiResult::ApiOk(body));
+    }
+    // Fields other than the status, like the data, are skipped without being decoded.
+    match serde_json::from_slice::<Status>(&body).map(|s| s.status) {
+        Ok("success") => Ok(RawApiResult::ApiOk(body)),
+        Ok("error") => Ok(RawApiResult::ApiErr(body)),
+        Ok(other) => Err(ProqError::UnexpectedData(format!(
+            "response status: {}",
+            other
+        ))),
+        Err(e) => Err(decode_error(&body, e.to_string())),
+    }
+}
+
+///
+/// Error of a request which didn't yield a response.
+///
+/// Failures to connect are told apart, nothing was sent then and any request can be retried.
+pub(crate) fn connection_error(e: surf::Exception) -> ProqError {
+    match e.downcast_ref::<isahc::Error>().map(isahc::Error::kind) {
+        Some(isahc::error::ErrorKind::ConnectionFailed)
+        | Some(isahc::error::ErrorKind::NameResolution) => ProqError::ConnectFailed(e.to_string()),
+        _ => ProqError::ConnectionError(e.to_string()),
+    }
+}
+
+///
+/// Whether the response is a `503 Service Unavailable` with `unavailable` error type,
+/// which Prometheus returns when it is overloaded.
+pub(crate) fn is_overloaded(status: StatusCode, body: &[u8]) -> bool {
+    status == StatusCode::SERVICE_UNAVAILABLE
+        && matches!(
+            decode_json(body),
+            Ok(ApiResult::ApiErr(err)) if err.error_type == PROQ_ERROR_TYPE_UNAVAILABLE
+        )
+}
+
+///
+/// Error of a non-success response.
+///
+/// `503 Service Unavailable` responses with `unavailable` error type are
+/// [ProqError::ServerOverloaded], others are [ProqError::UnexpectedStatus] with the raw body.
+pub(crate) fn status_error(status: StatusCode, body: &[u8]) -> ProqError {
+    if status == StatusCode::SERVICE_UNAVAILABLE {
+        if let Ok(ApiResult::ApiErr(err)) = decode_json(body) {
+            if err.error_type == PROQ_ERROR_TYPE_UNAVAILABLE {
+                return ProqError::ServerOverloaded {
+                    error_message: err.error_message,
+                };
+            }
+        }
+    }
+
+    ProqError::UnexpectedStatus {
+        status: status.as_u16(),
+        body: String::from_utf8_lossy(body).into_owned(),
+    }
+}
@@ -5,6 +5,16 @@ pub mod prometheus_types {
     //!
     //! Constants that helps Proq to interpret Prometheus return types.
     pub const PROQ_INFINITY: &str = "Inf";
+    pub const PROQ_POSITIVE_INFINITY: &str = "+Inf";
     pub const PROQ_NEGATIVE_INFINITY: &str = "-Inf";
     pub const PROQ_NAN: &str = "NaN";
+    pub const PROQ_BUCKET_LABEL: &str = "le";
+    pub const PROQ_METRIC_NAME_LABEL: &str = "__name__";
+    pub const PROQ_JOB_LABEL: &str = "job";
+    pub const PROQ_ERROR_TYPE_UNAVAILABLE: &str = "unavailable";
+    pub const PROQ_QUERY_ID_HEADER: &str = "X-Query-Id";
+    pub const PROQ_ADMIN_APIS_DISABLED: &str = "admin APIs disabled";
+    pub const PROQ_OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text";
+    pub const PROQ_SCRAPE_ACCEPT: &str =
+        "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5,*/*;q=0.1";
 }
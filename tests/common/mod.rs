@@ -0,0 +1,398 @@
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+
+///
+/// Serve the given raw HTTP responses to consecutive connections on a local port.
+///
+/// Returns the `localhost:<port>` host of the mock server.
+pub fn mock_server(responses: Vec<String>) -> String {
+    let mut responses = responses.into_iter();
+    let count = responses.len();
+    mock_server_with(count, move |_| responses.next().unwrap())
+}
+
+///
+/// Serve `count` connections on a local port answering each raw HTTP request with `handler`.
+///
+/// Returns the `localhost:<port>` host of the mock server.
+pub fn mock_server_with<R: Into<Vec<u8>>>(
+    count: usize,
+    mut handler: impl FnMut(&str) -> R + Send + 'static,
+) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for _ in 0..count {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_request(&mut stream);
+            let _ = stream.write_all(&handler(&request).into());
+        }
+    });
+
+    format!("localhost:{}", port)
+}
+
+///
+/// Answer every request with a `200 OK` JSON `body`, keeping the connections alive.
+///
+/// Returns the `localhost:<port>` host of the mock server and the count of accepted
+/// connections.
+pub fn keep_alive_server(body: &str) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    let accepted = connections.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+            let response = response.clone();
+            thread::spawn(move || {
+                while !read_request(&mut stream).is_empty() {
+                    if stream.write_all(response.as_bytes()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    (format!("localhost:{}", port), connections)
+}
+
+///
+/// Send the start of a raw HTTP response to one connection, then stall until the client
+/// closes it.
+///
+/// Returns the `localhost:<port>` host of the mock server.
+pub fn stalled_server(partial_response: String) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        read_request(&mut stream);
+        let _ = stream.write_all(partial_response.as_bytes());
+        let mut buf = [0_u8; 1024];
+        while let Ok(n) = stream.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+        }
+    });
+
+    format!("localhost:{}", port)
+}
+
+///
+/// Certificate and its private key for TLS mock servers.
+pub struct TestCert {
+    pub cert: X509,
+    pub key: PKey<Private>,
+}
+
+///
+/// Generate a certificate for `localhost`, self-signed if there is no `issuer`.
+pub fn localhost_cert(issuer: Option<&TestCert>) -> TestCert {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+
+    let mut serial = BigNum::new().unwrap();
+    serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&serial.to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder
+        .set_issuer_name(issuer.map_or(name.as_ref(), |i| i.cert.subject_name()))
+        .unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .build(&builder.x509v3_context(issuer.map(|i| i.cert.as_ref()), None))
+        .unwrap();
+    builder.append_extension(san).unwrap();
+    builder
+        .sign(issuer.map_or(&key, |i| &i.key), MessageDigest::sha256())
+        .unwrap();
+
+    TestCert {
+        cert: builder.build(),
+        key,
+    }
+}
+
+///
+/// Generate a self-signed certificate authority.
+pub fn ca_cert() -> TestCert {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "Proq Test CA").unwrap();
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder
+        .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    builder
+        .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+        .unwrap();
+    builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+    TestCert {
+        cert: builder.build(),
+        key,
+    }
+}
+
+///
+/// Write `contents` to a fresh file in the temporary directory and return its path.
+pub fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "proq-{}-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst),
+        name
+    ));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+///
+/// Serve the given raw HTTP responses over TLS with `cert` to consecutive connections.
+///
+/// Connections failing the TLS handshake don't take a response.
+/// Returns the `localhost:<port>` host of the mock server.
+pub fn mock_tls_server(cert: &TestCert, responses: Vec<String>) -> String {
+    serve_tls(cert, None, responses)
+}
+
+///
+/// Serve the given raw HTTP responses over TLS like [mock_tls_server], but require clients
+/// to present a certificate signed by `client_ca`.
+pub fn mock_mtls_server(cert: &TestCert, client_ca: &TestCert, responses: Vec<String>) -> String {
+    serve_tls(cert, Some(client_ca), responses)
+}
+
+fn serve_tls(cert: &TestCert, client_ca: Option<&TestCert>, responses: Vec<String>) -> String {
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&cert.key).unwrap();
+    acceptor.set_certificate(&cert.cert).unwrap();
+    if let Some(ca) = client_ca {
+        acceptor.cert_store_mut().add_cert(ca.cert.clone()).unwrap();
+        acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+    let acceptor = acceptor.build();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        let mut responses = responses.into_iter();
+        for stream in listener.incoming() {
+            let mut stream = match acceptor.accept(stream.unwrap()) {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // Client may reject the certificate only after the handshake.
+            if read_request(&mut stream).is_empty() {
+                continue;
+            }
+            match responses.next() {
+                Some(response) => {
+                    let _ = stream.write_all(response.as_bytes());
+                    let _ = stream.shutdown();
+                }
+                None => break,
+            }
+        }
+    });
+
+    format!("localhost:{}", port)
+}
+
+///
+/// Build a raw HTTP response with JSON body.
+pub fn json_response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+///
+/// Build a raw HTTP response with JSON body sent in chunks of `chunk_size` bytes.
+pub fn chunked_json_response(status: &str, body: &str, chunk_size: usize) -> String {
+    let chunks: String = body
+        .as_bytes()
+        .chunks(chunk_size)
+        .map(|c| format!("{:x}\r\n{}\r\n", c.len(), String::from_utf8_lossy(c)))
+        .collect();
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{}0\r\n\r\n",
+        status, chunks
+    )
+}
+
+///
+/// Build a raw HTTP response with gzip encoded JSON body.
+pub fn gzip_json_response(status: &str, body: &str) -> Vec<u8> {
+    let mut gzip = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    gzip.extend(miniz_oxide::deflate::compress_to_vec(body.as_bytes(), 6));
+    gzip.extend(&crc32(body.as_bytes()).to_le_bytes());
+    gzip.extend(&(body.len() as u32).to_le_bytes());
+
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        gzip.len()
+    )
+    .into_bytes();
+    response.extend(gzip);
+    response
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn read_request(stream: &mut (impl Read + Write)) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0_u8; 1024];
+
+    let end = loop {
+        if let Some(end) = header_end(&request) {
+            break end;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return String::from_utf8_lossy(&request).into_owned(),
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&request[..end]).to_lowercase();
+    if headers.contains("expect: 100-continue") {
+        let _ = stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n");
+    }
+    let chunked = headers.contains("transfer-encoding: chunked");
+    let length = header_value(&headers, "content-length")
+        .and_then(|l| l.parse().ok())
+        .unwrap_or(0);
+
+    loop {
+        let body = &request[end..];
+        let complete = if chunked {
+            body.ends_with(b"0\r\n\r\n")
+        } else {
+            body.len() >= length
+        };
+        if complete {
+            break;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(&request[end..])
+    } else {
+        request[end..].to_vec()
+    };
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&request[..end]),
+        String::from_utf8_lossy(&body)
+    )
+}
+
+fn decode_chunked(mut body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    while let Some(line_end) = body.windows(2).position(|w| w == b"\r\n") {
+        let size = usize::from_str_radix(String::from_utf8_lossy(&body[..line_end]).trim(), 16)
+            .unwrap_or(0);
+        if size == 0 {
+            break;
+        }
+        let start = line_end + 2;
+        decoded.extend_from_slice(&body[start..start + size]);
+        body = &body[start + size + 2..];
+    }
+    decoded
+}
+
+fn header_end(request: &[u8]) -> Option<usize> {
+    request
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|p| p + 4)
+}
+
+fn header_value<'a>(headers: &'a str, name: &str) -> Option<&'a str> {
+    headers.lines().find_map(|l| {
+        let mut parts = l.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(n), Some(value)) if n.trim() == name => Some(value.trim()),
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use proq::errors::ProqError;
+use proq::exposition::{parse_exposition, MetricKind, ScrapedMetric};
+
+fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+        .collect()
+}
+
+#[test]
+fn should_parse_text_exposition_with_type_and_help() {
+    let body = r#"
+# HELP http_requests_total The total number of HTTP requests.
+# TYPE http_requests_total counter
+http_requests_total{method="post",code="200"} 1027 1395066363000
+http_requests_total{method="post",code="400"}    3 1395066363000
+
+# A normal comment.
+# HELP msdos_file_access_time_seconds Escaped \\ help\nover lines.
+# TYPE msdos_file_access_time_seconds gauge
+msdos_file_access_time_seconds{path="C:\\DIR\\FILE.TXT",error="Cannot find file:\n\"FILE.TXT\""} 1.458255915e9
+
+# TYPE http_request_duration_seconds histogram
+http_request_duration_seconds_bucket{le="0.05"} 24054
+http_request_duration_seconds_bucket{le="+Inf"} 144320
+http_request_duration_seconds_sum 53423
+http_request_duration_seconds_count 144320
+
+metric_without_timestamp_and_labels 12.47
+"#;
+
+    let metrics = parse_exposition(body, false).unwrap();
+    assert_eq!(metrics.len(), 8);
+
+    assert_eq!(
+        metrics[0],
+        ScrapedMetric {
+            name: "http_requests_total".to_owned(),
+            labels: labels(&[("method", "post"), ("code", "200")]),
+            value: 1027.0,
+            timestamp: Some(1_395_066_363_000),
+            kind: MetricKind::Counter,
+            help: Some("The total number of HTTP requests.".to_owned()),
+        }
+    );
+    assert_eq!(metrics[1].value, 3.0);
+
+    assert_eq!(metrics[2].kind, MetricKind::Gauge);
+    assert_eq!(
+        metrics[2].help.as_deref(),
+        Some("Escaped \\ help\nover lines.")
+    );
+    assert_eq!(
+        metrics[2].labels,
+        labels(&[
+            ("path", "C:\\DIR\\FILE.TXT"),
+            ("error", "Cannot find file:\n\"FILE.TXT\""),
+        ])
+    );
+    assert_eq!(metrics[2].value, 1.458255915e9);
+
+    assert!(metrics[3..7]
+        .iter()
+        .all(|m| m.kind == MetricKind::Histogram && m.help.is_none()));
+    assert_eq!(metrics[4].labels, labels(&[("le", "+Inf")]));
+
+    assert_eq!(metrics[7].kind, MetricKind::Unknown);
+    assert!(metrics[7].labels.is_empty());
+    assert_eq!(metrics[7].timestamp, None);
+}
+
+#[test]
+fn should_parse_openmetrics_exposition() {
+    let body = r#"# TYPE acme_http_router_request_seconds summary
+# UNIT acme_http_router_request_seconds seconds
+# HELP acme_http_router_request_seconds Latency though all of ACME's HTTP request router.
+acme_http_router_request_seconds_sum{path="/api/v1",method="GET"} 9036.32 1520879607.789
+acme_http_router_request_seconds_count{path="/api/v1",method="GET"} 807283.0 1520879607.789
+# TYPE foo counter
+foo_total 17.0 1520879607.789 # {trace_id="KOO5S4vxi0o"} 0.67
+# EOF
+ignored 1
+"#;
+
+    let metrics = parse_exposition(body, true).unwrap();
+    assert_eq!(metrics.len(), 3);
+    assert!(metrics[..2]
+        .iter()
+        .all(|m| m.kind == MetricKind::Summary && m.timestamp == Some(1_520_879_607_789)));
+    assert_eq!(metrics[2].name, "foo_total");
+    assert_eq!(metrics[2].kind, MetricKind::Counter);
+    assert_eq!(metrics[2].value, 17.0);
+}
+
+#[test]
+fn should_reject_malformed_samples_with_line_number() {
+    for body in &[
+        "up{job=\"prometheus\" 1",
+        "up{job=prometheus} 1",
+        "up",
+        "up one",
+        "up 1 2 3",
+    ] {
+        match parse_exposition(&format!("# TYPE up gauge\n{}\n", body), false) {
+            Err(ProqError::UnexpectedData(message)) => {
+                assert!(message.starts_with("exposition line 2:"), "{}", message)
+            }
+            other => panic!("expected error for {}, got: {:?}", body, other),
+        }
+    }
+}
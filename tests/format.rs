@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use proq::format::{datetime_to_timestamp, duration_to_step, duration_to_timeout};
+
+#[test]
+fn should_format_step_as_float_seconds() {
+    assert_eq!(duration_to_step(Duration::from_millis(250)), 0.25);
+    assert_eq!(duration_to_step(Duration::from_micros(1500)), 0.0015);
+    assert_eq!(duration_to_step(Duration::from_secs(15)), 15_f64);
+    assert_eq!(
+        duration_to_step(Duration::from_secs(30 * 24 * 60 * 60)),
+        2_592_000_f64
+    );
+}
+
+#[test]
+fn should_format_datetime_as_float_seconds() {
+    let time = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+    assert_eq!(datetime_to_timestamp(time), 1_435_781_451_f64);
+    let time = Utc.timestamp_millis_opt(1_435_781_451_005).unwrap();
+    assert_eq!(datetime_to_timestamp(time), 1_435_781_451.005);
+    let time = Utc.timestamp_millis_opt(-1_500).unwrap();
+    assert_eq!(datetime_to_timestamp(time), -1.5);
+}
+
+#[test]
+fn should_format_timeout_as_duration_string() {
+    assert_eq!(duration_to_timeout(Duration::from_secs(0)), "0s");
+    assert_eq!(duration_to_timeout(Duration::from_millis(250)), "250ms");
+    assert_eq!(duration_to_timeout(Duration::from_millis(1500)), "1s500ms");
+    assert_eq!(duration_to_timeout(Duration::from_micros(1500)), "1ms");
+    assert_eq!(duration_to_timeout(Duration::from_secs(5)), "5s");
+    assert_eq!(duration_to_timeout(Duration::from_secs(3600)), "1h");
+    assert_eq!(
+        duration_to_timeout(Duration::from_secs(
+            2 * 365 * 24 * 60 * 60 + 9 * 24 * 60 * 60 + 61
+        )),
+        "2y1w2d1m1s"
+    );
+}
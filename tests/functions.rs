@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use proq::functions::{
+    aggregate_vector, diff_vectors, group_histograms, histogram_quantile, sort_instants, AggOp,
+    Histogram, SortOrder,
+};
+use proq::result_types::{Instant, Metric, Sample};
+
+fn bucket(le: &str, count: f64) -> Instant {
+    job_bucket("prometheus", le, count)
+}
+
+fn job_labels(job: &str) -> HashMap<String, String> {
+    let mut labels: HashMap<String, String> = HashMap::new();
+    labels.insert(
+        "__name__".to_owned(),
+        "http_request_duration_seconds_bucket".to_owned(),
+    );
+    labels.insert("job".to_owned(), job.to_owned());
+    labels
+}
+
+fn job_bucket(job: &str, le: &str, count: f64) -> Instant {
+    let mut labels = job_labels(job);
+    labels.insert("le".to_owned(), le.to_owned());
+
+    Instant {
+        metric: Metric { labels },
+        sample: Sample {
+            epoch: 1435781451.781,
+            value: count,
+        },
+    }
+}
+
+fn latency_buckets() -> Vec<Instant> {
+    vec![
+        bucket("+Inf", 100_f64),
+        bucket("0.1", 10_f64),
+        bucket("1", 95_f64),
+        bucket("0.5", 40_f64),
+    ]
+}
+
+fn assert_close(actual: Option<f64>, expected: f64) {
+    let actual = actual.unwrap();
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{} is not close to {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn should_calculate_histogram_quantiles() {
+    let buckets = latency_buckets();
+
+    assert_close(
+        histogram_quantile(&buckets, 0.5),
+        0.5 + 0.5 * (10_f64 / 55_f64),
+    );
+    assert_close(
+        histogram_quantile(&buckets, 0.9),
+        0.5 + 0.5 * (50_f64 / 55_f64),
+    );
+    assert_close(histogram_quantile(&buckets, 0.99), 1_f64);
+    assert_close(histogram_quantile(&buckets, 0.05), 0.05);
+}
+
+#[test]
+fn should_handle_out_of_range_histogram_quantiles() {
+    let buckets = latency_buckets();
+
+    assert_eq!(histogram_quantile(&buckets, -0.1), Some(f64::NEG_INFINITY));
+    assert_eq!(histogram_quantile(&buckets, 1.1), Some(f64::INFINITY));
+    assert_eq!(histogram_quantile(&buckets, f64::NAN), None);
+}
+
+#[test]
+fn should_not_calculate_histogram_quantile_without_inf_bucket() {
+    let buckets = vec![bucket("0.1", 10_f64), bucket("0.5", 40_f64)];
+    assert_eq!(histogram_quantile(&buckets, 0.5), None);
+
+    let empty = vec![bucket("0.1", 0_f64), bucket("+Inf", 0_f64)];
+    assert_eq!(histogram_quantile(&empty, 0.5), None);
+}
+
+#[test]
+fn should_group_bucket_series_into_histograms() {
+    let buckets = vec![
+        job_bucket("node", "+Inf", 20_f64),
+        job_bucket("prometheus", "1", 95_f64),
+        job_bucket("node", "0.5", 15_f64),
+        job_bucket("prometheus", "+Inf", 100_f64),
+        job_bucket("prometheus", "0.1", 10_f64),
+        job_bucket("node", "0.1", 5_f64),
+        job_bucket("prometheus", "0.5", 40_f64),
+    ];
+
+    let histograms = group_histograms(buckets);
+    assert_eq!(
+        histograms,
+        vec![
+            Histogram {
+                labels: job_labels("node"),
+                buckets: vec![(0.1, 5_f64), (0.5, 15_f64), (f64::INFINITY, 20_f64)],
+            },
+            Histogram {
+                labels: job_labels("prometheus"),
+                buckets: vec![
+                    (0.1, 10_f64),
+                    (0.5, 40_f64),
+                    (1_f64, 95_f64),
+                    (f64::INFINITY, 100_f64),
+                ],
+            },
+        ]
+    );
+    assert_eq!(
+        histograms[1].quantile(0.9),
+        histogram_quantile(&latency_buckets(), 0.9)
+    );
+}
+
+fn up(instance: &str, value: f64) -> Instant {
+    let mut labels: HashMap<String, String> = HashMap::new();
+    labels.insert("__name__".to_owned(), "up".to_owned());
+    labels.insert("instance".to_owned(), instance.to_owned());
+
+    Instant {
+        metric: Metric { labels },
+        sample: Sample {
+            epoch: 1435781451.781,
+            value,
+        },
+    }
+}
+
+#[test]
+fn should_diff_instant_vectors() {
+    let baseline = vec![
+        up("localhost:9090", 1_f64),
+        up("localhost:9100", 1_f64),
+        up("localhost:9200", 1_f64),
+    ];
+    let current = vec![
+        up("localhost:9300", 1_f64),
+        up("localhost:9200", 1_f64),
+        up("localhost:9090", 0_f64),
+    ];
+
+    let diff = diff_vectors(&baseline, &current);
+    assert_eq!(diff.added, vec![up("localhost:9300", 1_f64)]);
+    assert_eq!(diff.removed, vec![up("localhost:9100", 1_f64)]);
+    assert_eq!(diff.deltas.len(), 2);
+
+    let changed = diff.changed();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].labels, up("localhost:9090", 0_f64).metric.labels);
+    assert_eq!(changed[0].baseline, 1_f64);
+    assert_eq!(changed[0].current, 0_f64);
+    assert_eq!(changed[0].delta(), -1_f64);
+}
+
+#[test]
+fn should_sort_instants_by_value_and_label() {
+    let mut instants = vec![
+        job_bucket("b", "1", 2_f64),
+        job_bucket("c", "1", f64::NAN),
+        job_bucket("a", "1", 3_f64),
+        job_bucket("d", "1", 1_f64),
+    ];
+    let jobs = |instants: &[Instant]| -> Vec<String> {
+        instants
+            .iter()
+            .map(|i| i.metric.labels["job"].clone())
+            .collect()
+    };
+
+    sort_instants(&mut instants, &SortOrder::ValueDescending);
+    assert_eq!(jobs(&instants), vec!["a", "b", "d", "c"]);
+
+    sort_instants(&mut instants, &SortOrder::ValueAscending);
+    assert_eq!(jobs(&instants), vec!["d", "b", "a", "c"]);
+
+    sort_instants(&mut instants, &SortOrder::Label("job".to_owned()));
+    assert_eq!(jobs(&instants), vec!["a", "b", "c", "d"]);
+
+    sort_instants(&mut instants, &SortOrder::Unsorted);
+    assert_eq!(jobs(&instants), vec!["a", "b", "c", "d"]);
+}
+
+fn job_up(job: &str, instance: &str, value: f64) -> Instant {
+    let mut instant = up(instance, value);
+    instant
+        .metric
+        .labels
+        .insert("job".to_owned(), job.to_owned());
+    instant
+}
+
+fn aggregated(labels: &[(&str, &str)], value: f64) -> Instant {
+    Instant {
+        metric: Metric {
+            labels: labels
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+        },
+        sample: Sample {
+            epoch: 1435781451.781,
+            value,
+        },
+    }
+}
+
+#[test]
+fn should_aggregate_vector_by_labels() {
+    let vector = vec![
+        job_up("node", "localhost:9100", 1_f64),
+        job_up("prometheus", "localhost:9090", 1_f64),
+        job_up("node", "localhost:9101", 0_f64),
+        job_up("node", "localhost:9102", 1_f64),
+    ];
+
+    assert_eq!(
+        aggregate_vector(&vector, AggOp::Sum, &["job"]),
+        vec![
+            aggregated(&[("job", "node")], 2_f64),
+            aggregated(&[("job", "prometheus")], 1_f64),
+        ]
+    );
+    assert_eq!(
+        aggregate_vector(&vector, AggOp::Count, &["job"]),
+        vec![
+            aggregated(&[("job", "node")], 3_f64),
+            aggregated(&[("job", "prometheus")], 1_f64),
+        ]
+    );
+    assert_eq!(
+        aggregate_vector(&vector, AggOp::Avg, &[]),
+        vec![aggregated(&[], 0.75)]
+    );
+    assert_eq!(
+        aggregate_vector(&vector, AggOp::Min, &["job", "missing"]),
+        vec![
+            aggregated(&[("job", "node")], 0_f64),
+            aggregated(&[("job", "prometheus")], 1_f64),
+        ]
+    );
+}
+
+#[test]
+fn should_handle_nan_in_vector_aggregation() {
+    let vector = vec![
+        job_up("node", "localhost:9100", 2_f64),
+        job_up("node", "localhost:9101", f64::NAN),
+        job_up("node", "localhost:9102", 4_f64),
+    ];
+    let value = |op: AggOp| aggregate_vector(&vector, op, &["job"])[0].sample.value;
+
+    assert!(value(AggOp::Sum).is_nan());
+    assert!(value(AggOp::Avg).is_nan());
+    assert_eq!(value(AggOp::Min), 2_f64);
+    assert_eq!(value(AggOp::Max), 4_f64);
+    assert_eq!(value(AggOp::Count), 3_f64);
+
+    let all_nan = vec![up("localhost:9090", f64::NAN)];
+    assert!(aggregate_vector(&all_nan, AggOp::Max, &[])[0]
+        .sample
+        .value
+        .is_nan());
+}
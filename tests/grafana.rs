@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use proq::grafana::{GrafanaQuery, RangeQueryParams};
+
+fn query(interval: Duration, max_data_points: u64, hours: i64) -> GrafanaQuery {
+    let to = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+    GrafanaQuery {
+        expr: "rate(http_requests_total[5m])".to_string(),
+        interval,
+        max_data_points,
+        from: to - chrono::Duration::hours(hours),
+        to,
+    }
+}
+
+#[test]
+fn should_map_grafana_query_to_range_query_params() {
+    let grafana = query(Duration::from_secs(15), 1000, 1);
+
+    assert_eq!(
+        grafana.range_params(),
+        RangeQueryParams {
+            query: "rate(http_requests_total[5m])".to_string(),
+            start: Some(Utc.timestamp_opt(1_435_777_851, 0).unwrap()),
+            end: Some(Utc.timestamp_opt(1_435_781_451, 0).unwrap()),
+            step: Some(Duration::from_secs(15)),
+        }
+    );
+}
+
+#[test]
+fn should_widen_step_to_max_data_points() {
+    // 6 hours over 1000 points
+    assert_eq!(
+        query(Duration::from_secs(15), 1000, 6).step(),
+        Duration::from_millis(21_600)
+    );
+    // Rounded up to whole milliseconds
+    assert_eq!(
+        query(Duration::from_millis(1), 7, 1).step(),
+        Duration::from_millis(514_286)
+    );
+}
+
+#[test]
+fn should_bound_step_by_prometheus_point_limit() {
+    // 11000 points at most, also without a data point limit
+    assert_eq!(
+        query(Duration::from_secs(1), 0, 24).step(),
+        Duration::from_millis(7_855)
+    );
+    assert_eq!(
+        query(Duration::from_secs(1), 100_000, 24).step(),
+        Duration::from_millis(7_855)
+    );
+}
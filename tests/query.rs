@@ -1,8 +1,10 @@
 use chrono::Utc;
 use once_cell::sync::OnceCell;
 use proq::api::{ProqClient, ProqProtocol};
+use proq::errors::ProqError;
 use proq::query_types::{ProqRulesType, ProqTargetStates};
 use proq::result_types::ApiResult::ApiOk;
+use proq::result_types::Data;
 use std::sync::Once;
 use std::time::Duration;
 
@@ -275,3 +277,54 @@ fn proq_flags() {
         assert!(x)
     });
 }
+
+#[test]
+fn proq_tsdb_stats() {
+    futures::executor::block_on(async {
+        let x = match client().tsdb_stats().await.unwrap() {
+            ApiOk(r) => {
+                dbg!(r);
+                true
+            }
+            e => {
+                dbg!(e);
+                false
+            }
+        };
+
+        assert!(x)
+    });
+}
+
+#[test]
+fn proq_metric_names() {
+    futures::executor::block_on(async {
+        let names = client().metric_names().await.unwrap();
+        dbg!(&names);
+
+        assert!(names.iter().any(|n| n == "up"))
+    });
+}
+
+#[test]
+fn proq_snapshot() {
+    futures::executor::block_on(async {
+        let x = match client().snapshot(true).await {
+            Ok(ApiOk(r)) => {
+                dbg!(&r);
+                matches!(r.data, Some(Data::Snapshot(_)))
+            }
+            // Admin APIs are disabled unless Prometheus runs with `--web.enable-admin-api`.
+            Err(ProqError::AdminApiDisabled { error_message }) => {
+                dbg!(error_message);
+                true
+            }
+            e => {
+                let _ = dbg!(e);
+                false
+            }
+        };
+
+        assert!(x)
+    });
+}
@@ -0,0 +1,1049 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use http::Method;
+
+use proq::api::{ProqClient, ProqClientBuilder, ProqProtocol};
+use proq::errors::{ProqError, ProqResult};
+use proq::query_types::{
+    PreparedRequest, ProqMatchEncoding, ProqTargetStates, SeriesRequest, ThanosOptions,
+};
+
+fn series_request() -> SeriesRequest {
+    SeriesRequest {
+        selectors: vec![
+            "up".to_owned(),
+            "process_start_time_seconds{job=\"prometheus\"}".to_owned(),
+        ],
+        start: Some(1_435_781_430),
+        end: Some(1_435_781_460),
+        timeout: None,
+    }
+}
+
+#[test]
+fn should_encode_selectors_as_repeated_match_keys() {
+    assert_eq!(
+        series_request().form_encode(ProqMatchEncoding::default()),
+        "match%5B%5D=up&match%5B%5D=process_start_time_seconds%7Bjob%3D%22prometheus%22%7D\
+         &start=1435781430&end=1435781460"
+    );
+}
+
+#[test]
+fn should_encode_selectors_as_comma_joined_match_value() {
+    assert_eq!(
+        series_request().form_encode(ProqMatchEncoding::CommaJoined),
+        "match%5B%5D=up%2Cprocess_start_time_seconds%7Bjob%3D%22prometheus%22%7D\
+         &start=1435781430&end=1435781460"
+    );
+}
+
+fn dry_run_client() -> ProqClient {
+    ProqClient::new_with_proto(
+        "localhost:9090",
+        ProqProtocol::HTTP,
+        Some(Duration::from_secs(5)),
+    )
+    .unwrap()
+}
+
+fn prepared(result: ProqResult<Option<PreparedRequest>>) -> PreparedRequest {
+    match result {
+        Ok(Some(request)) => request,
+        other => panic!("expected prepared request, got: {:?}", other),
+    }
+}
+
+#[test]
+fn should_prepare_instant_query_in_dry_run() {
+    futures::executor::block_on(async {
+        let eval_time = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.instant_query("up", Some(eval_time)).await })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/query?query=up&time=1435781451.0&timeout=5s"
+        );
+        assert_eq!(request.body, None);
+        assert!(request.headers.is_empty());
+    });
+}
+
+#[test]
+fn should_keep_sub_second_instant_query_time() {
+    futures::executor::block_on(async {
+        let eval_time = Utc.timestamp_millis_opt(1_435_781_451_781).unwrap();
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.instant_query("up", Some(eval_time)).await })
+                .await,
+        );
+
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&time=1435781451.781&timeout=5s")
+        );
+    });
+}
+
+#[test]
+fn should_prepare_range_query_in_dry_run() {
+    futures::executor::block_on(async {
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let start = end - chrono::Duration::seconds(30);
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.range_query(
+                        "up",
+                        Some(start),
+                        Some(end),
+                        Some(Duration::from_secs_f64(1.5)),
+                    )
+                    .await
+                })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/query_range?query=up&start=1435781430.0&end=1435781460.0&step=1.5&timeout=5s"
+        );
+    });
+}
+
+#[test]
+fn should_keep_sub_second_range_query_bounds() {
+    futures::executor::block_on(async {
+        let start = Utc.timestamp_millis_opt(1_435_781_430_500).unwrap();
+        let end = start + chrono::Duration::seconds(30);
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.range_query(
+                        "up",
+                        Some(start),
+                        Some(end),
+                        Some(Duration::from_millis(500)),
+                    )
+                    .await
+                })
+                .await,
+        );
+
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&start=1435781430.5&end=1435781460.5&step=0.5&timeout=5s")
+        );
+    });
+}
+
+#[test]
+fn should_override_query_timeout_per_query() {
+    futures::executor::block_on(async {
+        let client = dry_run_client();
+        let eval_time = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+        let request = prepared(
+            client
+                .prepare(|c| async move {
+                    c.instant_query_with_timeout("up", Some(eval_time), Duration::from_secs(120))
+                        .await
+                })
+                .await,
+        );
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&time=1435781451.0&timeout=2m")
+        );
+
+        let start = Utc.timestamp_opt(1_435_781_430, 0).unwrap();
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let request = prepared(
+            client
+                .prepare(|c| async move {
+                    c.range_query_with_timeout(
+                        "up",
+                        Some(start),
+                        Some(end),
+                        Some(Duration::from_secs(15)),
+                        Duration::from_millis(1500),
+                    )
+                    .await
+                })
+                .await,
+        );
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&start=1435781430.0&end=1435781460.0&step=15.0&timeout=1s500ms")
+        );
+
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.instant_query("up", Some(eval_time)).await })
+                .await,
+        );
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&time=1435781451.0&timeout=5s")
+        );
+    });
+}
+
+#[test]
+fn should_prepare_series_in_dry_run() {
+    futures::executor::block_on(async {
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.series(vec!["up"], None, None).await })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.url.as_str(), "http://localhost:9090/api/v1/series");
+        assert_eq!(request.body, Some("match%5B%5D=up".to_owned()));
+        assert_eq!(
+            request.headers.get("content-type").map(String::as_str),
+            Some("application/x-www-form-urlencoded")
+        );
+    });
+}
+
+#[test]
+fn should_prepare_filtered_label_names_in_dry_run() {
+    futures::executor::block_on(async {
+        let start = Utc.timestamp_opt(1_435_781_430, 0).unwrap();
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.label_names_filtered(vec!["up"], Some(start), Some(end))
+                        .await
+                })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.url.as_str(), "http://localhost:9090/api/v1/labels");
+        assert_eq!(
+            request.body,
+            Some("match%5B%5D=up&start=1435781430&end=1435781460".to_owned())
+        );
+    });
+}
+
+#[test]
+fn should_prepare_filtered_label_values_in_dry_run() {
+    futures::executor::block_on(async {
+        let start = Utc.timestamp_opt(1_435_781_430, 0).unwrap();
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.label_values_filtered(
+                        "instance",
+                        vec!["up{job=\"node\"}"],
+                        Some(start),
+                        Some(end),
+                    )
+                    .await
+                })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/label/instance/values\
+             ?match%5B%5D=up%7Bjob%3D%22node%22%7D&start=1435781430&end=1435781460"
+        );
+
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.label_values_filtered("odd/label name?", Vec::new(), None, None)
+                        .await
+                })
+                .await,
+        );
+        assert_eq!(
+            request.url.path(),
+            "/api/v1/label/odd%2Flabel%20name%3F/values"
+        );
+        assert_eq!(request.url.query(), None);
+    });
+}
+
+#[test]
+fn should_add_custom_headers_to_prepared_requests() {
+    futures::executor::block_on(async {
+        let request = prepared(
+            dry_run_client()
+                .with_header("X-Scope-OrgID", "tenant-1")
+                .with_header("Content-Type", "text/plain")
+                .prepare(|c| async move { c.series(vec!["up"], None, None).await })
+                .await,
+        );
+
+        assert_eq!(
+            request.headers.get("x-scope-orgid").map(String::as_str),
+            Some("tenant-1")
+        );
+        assert_eq!(
+            request.headers.get("content-type").map(String::as_str),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert!(!request.headers.contains_key("Content-Type"));
+    });
+}
+
+#[test]
+fn should_prepare_targets_with_state_in_dry_run() {
+    futures::executor::block_on(async {
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.targets_with_state(ProqTargetStates::DROPPED).await })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/targets?state=dropped"
+        );
+    });
+}
+
+#[test]
+fn should_reject_inverted_time_range_in_dry_run() {
+    futures::executor::block_on(async {
+        let start = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let end = start - chrono::Duration::seconds(30);
+
+        match dry_run_client()
+            .prepare(|c| async move { c.range_query("up", Some(start), Some(end), None).await })
+            .await
+        {
+            Err(ProqError::InvalidParameter(_)) => {}
+            other => panic!("expected invalid parameter, got: {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn should_reject_zero_step_in_dry_run() {
+    futures::executor::block_on(async {
+        match dry_run_client()
+            .prepare(|c| async move {
+                c.range_query("up", None, None, Some(Duration::from_secs(0)))
+                    .await
+            })
+            .await
+        {
+            Err(ProqError::InvalidParameter(_)) => {}
+            other => panic!("expected invalid parameter, got: {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn should_detect_http_scheme_from_url() {
+    futures::executor::block_on(async {
+        let client = ProqClient::try_from("http://prom.example.com:9090").unwrap();
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.label_names().await })
+                .await,
+        );
+
+        assert_eq!(
+            request.url.as_str(),
+            "http://prom.example.com:9090/api/v1/labels"
+        );
+    });
+}
+
+#[test]
+fn should_detect_https_scheme_from_url() {
+    futures::executor::block_on(async {
+        let client = ProqClient::try_from("https://prom.example.com").unwrap();
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.label_names().await })
+                .await,
+        );
+
+        assert_eq!(
+            request.url.as_str(),
+            "https://prom.example.com/api/v1/labels"
+        );
+    });
+}
+
+#[test]
+fn should_normalize_trailing_slashes_of_host() {
+    futures::executor::block_on(async {
+        for host in &["localhost:9090", "localhost:9090/", "localhost:9090//"] {
+            let client = ProqClient::new_with_proto(host, ProqProtocol::HTTP, None).unwrap();
+            let request = prepared(
+                client
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(request.url.as_str(), "http://localhost:9090/api/v1/labels");
+        }
+
+        for host in &["http://prom.example.com", "http://prom.example.com/"] {
+            let request = prepared(
+                ProqClient::try_from(*host)
+                    .unwrap()
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(
+                request.url.as_str(),
+                "http://prom.example.com/api/v1/labels"
+            );
+
+            let request = prepared(
+                ProqClient::new_with_proto(host, ProqProtocol::HTTP, None)
+                    .unwrap()
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(
+                request.url.as_str(),
+                "http://prom.example.com/api/v1/labels"
+            );
+        }
+
+        for host in &["localhost:9090/prometheus", "localhost:9090/prometheus/"] {
+            let client = ProqClient::new_with_proto(host, ProqProtocol::HTTP, None).unwrap();
+            let request = prepared(
+                client
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(
+                request.url.as_str(),
+                "http://localhost:9090/prometheus/api/v1/labels"
+            );
+
+            let request = prepared(
+                ProqClient::new_with_proto(host, ProqProtocol::HTTP, None)
+                    .unwrap()
+                    .with_route_prefix("/thanos/")
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(
+                request.url.as_str(),
+                "http://localhost:9090/thanos/api/v1/labels"
+            );
+        }
+    });
+}
+
+#[test]
+fn should_validate_and_normalize_host_at_construction() {
+    futures::executor::block_on(async {
+        for (host, protocol, expected) in [
+            (
+                "localhost",
+                ProqProtocol::HTTP,
+                "http://localhost/api/v1/labels",
+            ),
+            (
+                "LOCALHOST:9090",
+                ProqProtocol::HTTP,
+                "http://localhost:9090/api/v1/labels",
+            ),
+            (
+                "127.0.0.1:9090",
+                ProqProtocol::HTTPS,
+                "https://127.0.0.1:9090/api/v1/labels",
+            ),
+            (
+                "http://prom.example.com:9090",
+                ProqProtocol::HTTP,
+                "http://prom.example.com:9090/api/v1/labels",
+            ),
+            (
+                "[::1]:9090",
+                ProqProtocol::HTTP,
+                "http://[::1]:9090/api/v1/labels",
+            ),
+        ] {
+            let client = ProqClient::new_with_proto(host, protocol, None).unwrap();
+            let request = prepared(
+                client
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(request.url.as_str(), expected);
+        }
+    });
+
+    for host in [
+        "",
+        "not a host",
+        "localhost:port",
+        "localhost:99999",
+        "http://",
+    ] {
+        match ProqClient::new_with_proto(host, ProqProtocol::HTTP, None) {
+            Err(ProqError::UrlParseError(_)) => {}
+            Err(e) => panic!("expected URL parse error for {:?}, got: {:?}", host, e),
+            Ok(_) => panic!("expected URL parse error for {:?}", host),
+        }
+    }
+
+    for host in ["user:secret@localhost:9090", "localhost:9090?debug=1"] {
+        match ProqClient::new_with_proto(host, ProqProtocol::HTTP, None) {
+            Err(ProqError::InvalidParameter(message)) => assert!(!message.contains("secret")),
+            Err(e) => panic!("expected invalid parameter for {:?}, got: {:?}", host, e),
+            Ok(_) => panic!("expected invalid parameter for {:?}", host),
+        }
+    }
+}
+
+#[test]
+fn should_reject_host_scheme_mismatching_protocol() {
+    for (host, protocol, scheme, expected) in [
+        (
+            "http://localhost:9090",
+            ProqProtocol::HTTPS,
+            "http",
+            "https",
+        ),
+        (
+            "https://localhost:9090",
+            ProqProtocol::HTTP,
+            "https",
+            "http",
+        ),
+        ("ftp://localhost:9090", ProqProtocol::HTTP, "ftp", "http"),
+    ] {
+        match ProqClient::new_with_proto(host, protocol, None) {
+            Err(ProqError::ProtocolMismatch {
+                scheme: s,
+                protocol: p,
+            }) => {
+                assert_eq!(s, scheme);
+                assert_eq!(p, expected);
+            }
+            Err(e) => panic!("expected protocol mismatch, got: {:?}", e),
+            Ok(_) => panic!("expected protocol mismatch for {}", host),
+        }
+    }
+
+    futures::executor::block_on(async {
+        for (host, protocol, expected) in [
+            ("http://localhost:9090", ProqProtocol::HTTP, "http"),
+            ("HTTPS://localhost:9090", ProqProtocol::HTTPS, "https"),
+            ("https://localhost:9090/", ProqProtocol::HTTPS, "https"),
+        ] {
+            let client = ProqClient::new_with_proto(host, protocol, None).unwrap();
+            let request = prepared(
+                client
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(
+                request.url.as_str(),
+                format!("{}://localhost:9090/api/v1/labels", expected)
+            );
+        }
+    });
+}
+
+#[test]
+fn should_prepend_normalized_route_prefix() {
+    futures::executor::block_on(async {
+        for prefix in &[
+            "prometheus",
+            "/prometheus",
+            "/prometheus/",
+            "//prometheus//",
+        ] {
+            let request = prepared(
+                dry_run_client()
+                    .with_route_prefix(prefix)
+                    .prepare(|c| async move { c.label_values("job").await })
+                    .await,
+            );
+            assert_eq!(
+                request.url.as_str(),
+                "http://localhost:9090/prometheus/api/v1/label/job/values"
+            );
+        }
+
+        for prefix in &["", "/", "//"] {
+            let request = prepared(
+                dry_run_client()
+                    .with_route_prefix(prefix)
+                    .prepare(|c| async move { c.label_names().await })
+                    .await,
+            );
+            assert_eq!(request.url.as_str(), "http://localhost:9090/api/v1/labels");
+        }
+
+        let client = ProqClient::try_from("https://example.com/prometheus/").unwrap();
+        let eval_time = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.instant_query("up", Some(eval_time)).await })
+                .await,
+        );
+        assert_eq!(
+            request.url.as_str(),
+            "https://example.com/prometheus/api/v1/query?query=up&time=1435781451.0"
+        );
+    });
+}
+
+#[test]
+fn should_use_changed_query_timeout() {
+    futures::executor::block_on(async {
+        let mut client = dry_run_client();
+        assert_eq!(client.query_timeout(), Some(Duration::from_secs(5)));
+
+        client.set_query_timeout(Some(Duration::from_secs(90)));
+        assert_eq!(client.query_timeout(), Some(Duration::from_secs(90)));
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.instant_query("up", None).await })
+                .await,
+        );
+        assert_eq!(request.url.query(), Some("query=up&timeout=1m30s"));
+
+        client.set_query_timeout(None);
+        assert_eq!(client.query_timeout(), None);
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.instant_query("up", None).await })
+                .await,
+        );
+        assert_eq!(request.url.query(), Some("query=up"));
+    });
+}
+
+#[test]
+fn should_build_client_with_builder() {
+    futures::executor::block_on(async {
+        let client = ProqClient::builder()
+            .host("localhost:9090")
+            .protocol(ProqProtocol::HTTP)
+            .query_timeout(Some(Duration::from_secs(5)))
+            .bearer_token("s3cr3t")
+            .header("X-Scope-OrgID", "tenant-1")
+            .build()
+            .unwrap();
+        assert_eq!(client.query_timeout(), Some(Duration::from_secs(5)));
+
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.instant_query("up", None).await })
+                .await,
+        );
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/query?query=up&timeout=5s"
+        );
+        assert_eq!(
+            request.headers.get("x-scope-orgid").map(String::as_str),
+            Some("tenant-1")
+        );
+        assert!(!request.headers.contains_key("authorization"));
+
+        let request = prepared(
+            ProqClientBuilder::new()
+                .host("localhost:9090")
+                .build()
+                .unwrap()
+                .prepare(|c| async move { c.label_names().await })
+                .await,
+        );
+        assert_eq!(request.url.as_str(), "https://localhost:9090/api/v1/labels");
+    });
+
+    match ProqClient::builder().protocol(ProqProtocol::HTTP).build() {
+        Err(ProqError::InvalidParameter(_)) => {}
+        Err(e) => panic!("expected invalid parameter error, got: {:?}", e),
+        Ok(_) => panic!("expected missing host error"),
+    }
+}
+
+#[test]
+fn should_reject_invalid_url_string() {
+    assert!(ProqClient::try_from("not a url").is_err());
+    assert!(ProqClient::try_from("ftp://prom.example.com:9090").is_err());
+    match ProqClient::try_from("http://user:pw@prom.example.com?x=1") {
+        Err(ProqError::InvalidParameter(message)) => assert!(!message.contains("pw")),
+        other => panic!("expected invalid parameter, got: {:?}", other),
+    }
+    assert!(ProqClient::try_from("http://prom.example.com:9090/?x=1").is_err());
+}
+
+#[test]
+fn should_serialize_thanos_options_into_query_string() {
+    futures::executor::block_on(async {
+        let eval_time = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.instant_query_thanos("up", Some(eval_time), &ThanosOptions::default())
+                        .await
+                })
+                .await,
+        );
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&time=1435781451.0&timeout=5s")
+        );
+        assert_eq!(
+            serde_urlencoded::to_string(ThanosOptions::default()).unwrap(),
+            ""
+        );
+
+        let options = ThanosOptions {
+            dedup: Some(true),
+            partial_response: Some(false),
+            max_source_resolution: Some("5m".into()),
+            engine: None,
+        };
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.instant_query_thanos("up", Some(eval_time), &options)
+                        .await
+                })
+                .await,
+        );
+
+        assert_eq!(request.url.path(), "/api/v1/query");
+        assert_eq!(
+            request.url.query(),
+            Some(
+                "query=up&time=1435781451.0&timeout=5s\
+                 &dedup=true&partial_response=false&max_source_resolution=5m"
+            )
+        );
+    });
+}
+
+#[test]
+fn should_expose_url_parse_error_as_std_error_source() {
+    let err: Box<dyn std::error::Error> = match ProqClient::try_from("not a url") {
+        Err(e @ ProqError::UrlParseError(_)) => Box::new(e),
+        Err(e) => panic!("expected URL parse error, got: {:?}", e),
+        Ok(_) => panic!("expected invalid URL error"),
+    };
+
+    assert!(err.to_string().starts_with("Failed to parse URL: "));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn should_prepare_metric_names_matching_with_regex_selector() {
+    futures::executor::block_on(async {
+        match dry_run_client()
+            .prepare(|c| async move { c.metric_names_matching("http_.*").await })
+            .await
+        {
+            Ok(Some(request)) => {
+                assert_eq!(request.method, Method::GET);
+                assert_eq!(request.url.path(), "/api/v1/label/__name__/values");
+                assert_eq!(
+                    request.url.query(),
+                    Some("match%5B%5D=%7B__name__%3D%7E%22http_.*%22%7D")
+                );
+
+                let selectors: Vec<(String, String)> =
+                    request.url.query_pairs().into_owned().collect();
+                assert_eq!(
+                    selectors,
+                    vec![("match[]".to_owned(), "{__name__=~\"http_.*\"}".to_owned())]
+                );
+            }
+            other => panic!("expected dry run request, got: {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn should_use_default_range_for_unbounded_range_query() {
+    futures::executor::block_on(async {
+        let client = dry_run_client().with_default_range(Duration::from_secs(15 * 60));
+        let before = Utc::now().timestamp_millis();
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.range_query("up", None, None, None).await })
+                .await,
+        );
+        let after = Utc::now().timestamp_millis();
+
+        let millis = |name: &str| -> i64 {
+            request
+                .url
+                .query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| (v.parse::<f64>().unwrap() * 1000.0).round() as i64)
+                .unwrap()
+        };
+        let (start, end) = (millis("start"), millis("end"));
+
+        assert!(before <= end && end <= after);
+        assert_eq!(end - start, 15 * 60 * 1000);
+
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let request = prepared(
+            client
+                .prepare(|c| async move { c.range_query("up", None, Some(end), None).await })
+                .await,
+        );
+        assert_eq!(
+            request.url.query(),
+            Some("query=up&start=1435780560.0&end=1435781460.0&timeout=5s")
+        );
+    });
+}
+
+#[test]
+fn should_compute_range_window_step_from_points() {
+    futures::executor::block_on(async {
+        let before = Utc::now().timestamp_millis();
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.range_query_window("up", chrono::Duration::hours(6), 300)
+                        .await
+                })
+                .await,
+        );
+        let after = Utc::now().timestamp_millis();
+
+        let param = |name: &str| -> String {
+            request
+                .url
+                .query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+                .unwrap()
+        };
+        let millis = |name: &str| (param(name).parse::<f64>().unwrap() * 1000.0).round() as i64;
+        let (start, end) = (millis("start"), millis("end"));
+
+        assert!(before <= end && end <= after);
+        assert_eq!(end - start, 6 * 60 * 60 * 1000);
+        assert_eq!(param("step"), "72.0");
+
+        match dry_run_client()
+            .prepare(|c| async move {
+                c.range_query_window("up", chrono::Duration::hours(6), 0)
+                    .await
+            })
+            .await
+        {
+            Err(ProqError::InvalidParameter(_)) => {}
+            other => panic!("expected invalid parameter error, got: {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn should_validate_series_selectors_before_sending() {
+    futures::executor::block_on(async {
+        match dry_run_client()
+            .prepare(|c| async move {
+                c.series(
+                    vec!["up", "process_start_time_seconds{job=\"prometheus\""],
+                    None,
+                    None,
+                )
+                .await
+            })
+            .await
+        {
+            Err(ProqError::InvalidSelector { position, .. }) => assert_eq!(position, 43),
+            other => panic!("expected invalid selector, got: {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn should_prepare_metadata_request_in_dry_run() {
+    futures::executor::block_on(async {
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.metadata(Some("http_requests_total"), Some(10)).await })
+                .await,
+        );
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/metadata?metric=http_requests_total&limit=10"
+        );
+    });
+}
+
+#[test]
+fn should_prepare_target_metadata_request_in_dry_run() {
+    futures::executor::block_on(async {
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.target_metadata(Some("{job=\"prometheus\"}"), Some("go_goroutines"), Some(2))
+                        .await
+                })
+                .await,
+        );
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/targets/metadata?match_target=%7Bjob%3D%22prometheus%22%7D&metric=go_goroutines&limit=2"
+        );
+    });
+}
+
+#[test]
+fn should_request_exemplars_with_range_query_only_when_enabled() {
+    futures::executor::block_on(async {
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let start = end - chrono::Duration::seconds(30);
+        let step = Some(Duration::from_secs(15));
+
+        let request = prepared(
+            dry_run_client()
+                .with_range_exemplars(true)
+                .prepare(|c| async move { c.range_query("up", Some(start), Some(end), step).await })
+                .await,
+        );
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/query_range?query=up&start=1435781430.0&end=1435781460.0&step=15.0&timeout=5s&exemplar=true"
+        );
+
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.range_query("up", Some(start), Some(end), step).await })
+                .await,
+        );
+        assert!(!request.url.as_str().contains("exemplar"));
+    });
+}
+
+#[test]
+fn should_prepare_snapshot_request_in_dry_run() {
+    futures::executor::block_on(async {
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move { c.snapshot(true).await })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/admin/tsdb/snapshot"
+        );
+        assert_eq!(request.body.as_deref(), Some("skip_head=true"));
+    });
+}
+
+#[test]
+fn should_prepare_delete_series_request_in_dry_run() {
+    futures::executor::block_on(async {
+        let end = Utc.timestamp_opt(1_435_781_460, 0).unwrap();
+        let start = end - chrono::Duration::seconds(30);
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.delete_series(vec!["up{job=\"stale\"}"], Some(start), Some(end))
+                        .await
+                })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/admin/tsdb/delete_series"
+        );
+        assert_eq!(
+            request.body.as_deref(),
+            Some("match%5B%5D=up%7Bjob%3D%22stale%22%7D&start=1435781430&end=1435781460")
+        );
+    });
+}
+
+#[test]
+fn should_prepare_exemplars_query_in_dry_run() {
+    futures::executor::block_on(async {
+        let end = Utc.timestamp_millis_opt(1_600_096_960_781).unwrap();
+        let start = end - chrono::Duration::minutes(5);
+        let request = prepared(
+            dry_run_client()
+                .prepare(|c| async move {
+                    c.query_exemplars("test_exemplar_metric_total", start, end)
+                        .await
+                })
+                .await,
+        );
+
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9090/api/v1/query_exemplars?query=test_exemplar_metric_total&start=1600096660.781&end=1600096960.781"
+        );
+
+        match dry_run_client()
+            .prepare(|c| async move { c.query_exemplars("up", end, start).await })
+            .await
+        {
+            Err(ProqError::InvalidParameter(_)) => {}
+            other => panic!("expected invalid parameter error, got: {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn should_redact_credentials_in_debug_output() {
+    let client = ProqClient::new_with_proto(
+        "localhost:9090",
+        ProqProtocol::HTTP,
+        Some(Duration::from_secs(5)),
+    )
+    .unwrap()
+    .with_basic_auth("Aladdin", "open sesame")
+    .with_header("X-Scope-OrgID", "tenant-secret");
+
+    let debug = format!("{:?}", client);
+    assert!(debug.contains("localhost:9090"));
+    assert!(debug.contains("HTTP"));
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains("open sesame"));
+    assert!(!debug.contains("Aladdin"));
+    assert!(!debug.contains("tenant-secret"));
+
+    let debug = format!("{:?}", client.with_bearer_token("s3cr3t-t0ken"));
+    assert!(!debug.contains("s3cr3t-t0ken"));
+}
+
+#[test]
+fn should_prepare_nothing_for_call_without_request() {
+    futures::executor::block_on(async {
+        match dry_run_client()
+            .prepare(|_| async { Ok::<_, ProqError>(()) })
+            .await
+        {
+            Ok(None) => {}
+            other => panic!("expected no request, got: {:?}", other),
+        }
+    });
+}
@@ -0,0 +1,121 @@
+use proq::errors::ProqError;
+use proq::result_types::Metric;
+use proq::selector::{validate_selector, LabelSelector};
+
+fn error_position(selector: &str) -> usize {
+    match validate_selector(selector) {
+        Err(ProqError::InvalidSelector { position, .. }) => position,
+        other => panic!(
+            "expected invalid selector for {}, got: {:?}",
+            selector, other
+        ),
+    }
+}
+
+#[test]
+fn should_accept_valid_selectors() {
+    let selectors = vec![
+        "up",
+        "job:request_latency_seconds:mean5m",
+        "up{}",
+        "{__name__=~\"http_.*\"}",
+        "process_start_time_seconds{job=\"prometheus\"}",
+        "http_requests_total{job=\"api\", code=~\"5..\", method!=\"GET\", path!~'/internal/.*',}",
+        "  up { job = `node` }  ",
+        "up{msg=\"escaped \\\" quote\"}",
+    ];
+
+    for selector in selectors {
+        assert!(
+            validate_selector(selector).is_ok(),
+            "{} should be valid",
+            selector
+        );
+    }
+}
+
+#[test]
+fn should_reject_malformed_selectors() {
+    assert_eq!(error_position(""), 0);
+    assert_eq!(error_position("up{job=\"prometheus\""), 19);
+    assert_eq!(error_position("up{job=prometheus}"), 7);
+    assert_eq!(error_position("up{job=\"prometheus}"), 7);
+    assert_eq!(error_position("up{job==\"prometheus\"}"), 7);
+    assert_eq!(error_position("up{job~\"prometheus\"}"), 6);
+    assert_eq!(error_position("up{1job=\"prometheus\"}"), 3);
+    assert_eq!(error_position("up{job=\"a\" instance=\"b\"}"), 11);
+    assert_eq!(error_position("up}"), 2);
+    assert_eq!(error_position("9up"), 0);
+}
+
+#[test]
+fn should_build_and_render_metric() {
+    let metric = Metric::builder()
+        .name("http_requests_total")
+        .label("job", "api")
+        .label("code", "500")
+        .build();
+
+    assert_eq!(metric.name(), Some("http_requests_total"));
+    assert_eq!(
+        metric.to_string(),
+        "http_requests_total{code=\"500\",job=\"api\"}"
+    );
+    assert!(validate_selector(&metric.to_string()).is_ok());
+}
+
+#[test]
+fn should_mutate_metric_labels() {
+    let mut metric = Metric::builder()
+        .name("up")
+        .build()
+        .with_label("job", "prometheus")
+        .with_label("instance", "localhost:9090");
+
+    assert_eq!(
+        metric.to_string(),
+        "up{instance=\"localhost:9090\",job=\"prometheus\"}"
+    );
+
+    assert_eq!(
+        metric.remove_label("instance"),
+        Some("localhost:9090".to_owned())
+    );
+    assert_eq!(metric.remove_label("instance"), None);
+    assert_eq!(metric.to_string(), "up{job=\"prometheus\"}");
+
+    assert_eq!(metric.remove_label("job"), Some("prometheus".to_owned()));
+    assert_eq!(metric.to_string(), "up");
+
+    let unnamed = Metric::builder().label("path", "C:\\ \"quoted\"").build();
+    assert_eq!(unnamed.name(), None);
+    assert_eq!(unnamed.to_string(), "{path=\"C:\\\\ \\\"quoted\\\"\"}");
+    assert!(validate_selector(&unnamed.to_string()).is_ok());
+}
+
+#[test]
+fn should_filter_metrics_by_label_selector() {
+    let metric = Metric::builder()
+        .name("up")
+        .label("job", "node")
+        .label("instance", "localhost:9100")
+        .build();
+
+    assert!(LabelSelector::new().matches(&metric));
+    assert!(LabelSelector::new()
+        .equal("job", "node")
+        .not_equal("instance", "localhost:9090")
+        .matches(&metric));
+    assert!(!LabelSelector::new()
+        .equal("job", "node")
+        .equal("instance", "localhost:9090")
+        .matches(&metric));
+    assert!(LabelSelector::new()
+        .one_of("job", vec!["prometheus", "node"])
+        .matches(&metric));
+    assert!(!LabelSelector::new()
+        .one_of("job", Vec::<String>::new())
+        .matches(&metric));
+    assert!(LabelSelector::new().equal("env", "").matches(&metric));
+    assert!(!LabelSelector::new().not_equal("env", "").matches(&metric));
+}
@@ -0,0 +1,97 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use proq::api::{ProqClient, ProqProtocol};
+use proq::errors::ProqResult;
+use proq::functions::SortOrder;
+use proq::query_types::{ProqRulesType, ProqTargetStates};
+use proq::result_types::{ApiResult, ResponseMeta};
+use proq::selector::LabelSelector;
+
+type BoxedQuery<'a, T> = Pin<Box<dyn Future<Output = ProqResult<T>> + Send + 'a>>;
+
+fn assert_send<T: Send>(_: &T) {}
+
+fn assert_sync<T: Sync>() {}
+
+fn client() -> ProqClient {
+    ProqClient::new_with_proto(
+        "localhost:9090",
+        ProqProtocol::HTTP,
+        Some(Duration::from_secs(5)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn client_should_be_send_and_sync() {
+    assert_send(&client());
+    assert_sync::<ProqClient>();
+}
+
+#[test]
+fn query_futures_should_be_send() {
+    let client = client();
+    let end = Utc::now();
+    let start = Some(end - chrono::Duration::minutes(1));
+
+    let futures: Vec<BoxedQuery<ApiResult>> = vec![
+        Box::pin(client.instant_query("up", None)),
+        Box::pin(client.range_query("up", start, Some(end), None)),
+        Box::pin(client.range_query_window("up", chrono::Duration::hours(6), 300)),
+        Box::pin(client.series(vec!["up"], start, Some(end))),
+        Box::pin(client.query_exemplars("up", end - chrono::Duration::minutes(1), end)),
+        Box::pin(client.label_names()),
+        Box::pin(client.label_names_filtered(vec!["up"], start, Some(end))),
+        Box::pin(client.label_values("job")),
+        Box::pin(client.label_values_filtered("job", vec!["up"], start, Some(end))),
+        Box::pin(client.targets()),
+        Box::pin(client.targets_with_state(ProqTargetStates::ACTIVE)),
+        Box::pin(client.rules()),
+        Box::pin(client.rules_with_type(ProqRulesType::ALERT)),
+        Box::pin(client.alerts()),
+        Box::pin(client.alert_managers()),
+        Box::pin(client.config()),
+        Box::pin(client.flags()),
+        Box::pin(client.tsdb_stats()),
+        Box::pin(client.build_info()),
+        Box::pin(client.wal_replay()),
+        Box::pin(client.runtime_info()),
+        Box::pin(client.metadata(Some("up"), None)),
+        Box::pin(client.target_metadata(None, Some("up"), None)),
+    ];
+    for f in &futures {
+        assert_send(f);
+    }
+
+    let filter = LabelSelector::new().equal("job", "node");
+    assert_send(&client.instant_query_filtered("up", None, &filter, SortOrder::ValueDescending));
+
+    let names: Vec<BoxedQuery<Vec<String>>> = vec![
+        Box::pin(client.metric_names()),
+        Box::pin(client.metric_names_matching("http_.*")),
+    ];
+    for f in &names {
+        assert_send(f);
+    }
+
+    assert_send(&client.scrape("/metrics"));
+
+    let with_meta: Vec<BoxedQuery<(ApiResult, ResponseMeta)>> = vec![
+        Box::pin(client.instant_query_with_meta("up", None)),
+        Box::pin(client.config_with_etag(None)),
+        Box::pin(client.rules_with_etag(None)),
+    ];
+    for f in &with_meta {
+        assert_send(f);
+    }
+}
+
+#[test]
+fn series_stream_should_be_send() {
+    let client = client();
+    assert_send(&client.series_stream(vec!["up"], None, None));
+}
@@ -1,13 +1,20 @@
+// Samples of the original tests spell their values as casts.
+#![allow(clippy::unnecessary_cast)]
+
 use std::collections::HashMap;
 use std::result::Result as StdResult;
+use std::time::Duration;
 
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use url::Url;
 
+use proq::errors::ProqError;
 use proq::result_types::{
-    ActiveTarget, Alert, AlertManager, AlertManagers, AlertState, ApiErr, ApiOk, ApiResult, Config,
-    Data, DroppedTarget, Expression, Instant, LabelsOrValues, Metric, Range, Rule, RuleGroups,
-    RuleType, Rules, Sample, Series, Snapshot, StringSample, TargetHealth, Targets,
+    ActiveTarget, Alert, AlertManager, AlertManagers, AlertState, ApiErr, ApiOk, ApiResult,
+    BackendKind, BuildInfo, Config, Data, DroppedTarget, Exemplar, ExemplarSet, Expression,
+    Instant, LabelChange, LabelsOrValues, Metric, MetricMetadata, Range, Rule, RuleGroups,
+    RuleType, Rules, RuntimeInfo, Sample, Series, Snapshot, StringSample, TargetHealth,
+    TargetMetadataEntry, Targets, TsdbStat, WalReplay, WalReplayState, Warning,
 };
 
 #[test]
@@ -109,6 +116,76 @@ fn should_deserialize_json_error_with_instant_and_warnings() -> StdResult<(), st
     Ok(())
 }
 
+#[test]
+fn should_return_partial_data_of_api_error() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status": "error",
+            "error": "query processing would load too many samples into memory",
+            "errorType": "execution",
+            "data" : {
+                "resultType" : "vector",
+                "result" : [
+                    {
+                        "metric" : { "__name__" : "up", "job" : "prometheus" },
+                        "value": [ 1435781451.781, "1" ]
+                    },
+                    {
+                        "metric" : { "__name__" : "up", "job" : "node" },
+                        "value" : [ 1435781451.781, "0" ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+    match serde_json::from_str::<ApiResult>(j)? {
+        ApiResult::ApiErr(err) => match err.partial_data() {
+            Some(Data::Expression(Expression::Instant(instants))) => {
+                assert_eq!(instants.len(), 2);
+                assert_eq!(instants[1].metric.labels["job"], "node");
+            }
+            data => panic!("expected partial instant vector, got: {:?}", data),
+        },
+        res => panic!("expected error, got: {:?}", res),
+    }
+
+    let without_data = r#"{"status": "error", "error": "Major", "errorType": "Seriously Bad"}"#;
+    match serde_json::from_str::<ApiResult>(without_data)? {
+        ApiResult::ApiErr(err) => assert_eq!(err.partial_data(), None),
+        res => panic!("expected error, got: {:?}", res),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_display_api_error() {
+    let mut err = ApiErr {
+        error_type: "bad_data".to_string(),
+        error_message: "invalid parameter \"query\": 1:3: parse error".to_string(),
+        data: None,
+        warnings: Vec::new(),
+    };
+    assert_eq!(
+        err.to_string(),
+        "bad_data: invalid parameter \"query\": 1:3: parse error"
+    );
+
+    err.warnings = vec![
+        "PromQL info: metric might not be a counter".to_string(),
+        "partial response".to_string(),
+    ];
+    assert_eq!(
+        err.to_string(),
+        "bad_data: invalid parameter \"query\": 1:3: parse error \
+         (warnings: PromQL info: metric might not be a counter; partial response)"
+    );
+
+    let boxed: Box<dyn std::error::Error> = Box::new(err);
+    assert!(boxed.to_string().starts_with("bad_data: "));
+}
+
 #[test]
 fn should_deserialize_json_prom_scalar() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -136,6 +213,60 @@ fn should_deserialize_json_prom_scalar() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
+#[test]
+fn should_access_scalar_data() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "resultType": "scalar",
+                "result": [1435781451.781, "1"]
+            }
+        }
+        "#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    assert_eq!(
+        ok.as_scalar(),
+        Some(&Sample {
+            epoch: 1435781451.781,
+            value: 1_f64,
+        })
+    );
+    assert_eq!(ok.as_string_sample(), None);
+    assert_eq!(ok.as_instant_vector(), None);
+
+    Ok(())
+}
+
+#[test]
+fn should_round_trip_special_sample_values() -> StdResult<(), serde_json::Error> {
+    let sample = |value: f64| Sample {
+        epoch: 1435781451.781,
+        value,
+    };
+
+    for (value, json) in [
+        (f64::INFINITY, r#"[1435781451.781,"+Inf"]"#),
+        (f64::NEG_INFINITY, r#"[1435781451.781,"-Inf"]"#),
+        (f64::NAN, r#"[1435781451.781,"NaN"]"#),
+        (0.25, r#"[1435781451.781,"0.25"]"#),
+    ] {
+        let s = serde_json::to_string(&sample(value))?;
+        assert_eq!(s, json);
+
+        let parsed = serde_json::from_str::<Sample>(&s)?;
+        assert_eq!(parsed.epoch, 1435781451.781);
+        if value.is_nan() {
+            assert!(parsed.value.is_nan());
+        } else {
+            assert_eq!(parsed.value, value);
+        }
+    }
+
+    Ok(())
+}
+
 #[test]
 fn should_deserialize_json_prom_scalar_with_warnings() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -191,6 +322,25 @@ fn should_deserialize_json_prom_string() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
+#[test]
+fn should_access_string_data() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "resultType": "string",
+                "result": [1435781451.781, "foo"]
+            }
+        }
+        "#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    assert_eq!(ok.as_string_sample().map(|s| s.value.as_str()), Some("foo"));
+    assert_eq!(ok.as_scalar(), None);
+
+    Ok(())
+}
+
 #[test]
 fn should_deserialize_json_prom_vector() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -261,6 +411,93 @@ fn should_deserialize_json_prom_vector() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
+#[test]
+fn should_access_vector_data() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" : {
+                "resultType" : "vector",
+                "result" : [
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "prometheus",
+                            "instance" : "localhost:9090"
+                        },
+                        "value": [ 1435781451.781, "1" ]
+                    },
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "node",
+                            "instance" : "localhost:9100"
+                        },
+                        "value" : [ 1435781451.781, "0" ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    let instants = ok.as_instant_vector().unwrap();
+    assert_eq!(instants.len(), 2);
+    assert_eq!(instants[0].metric.labels["job"], "prometheus");
+    assert_eq!(ok.as_range_matrix(), None);
+    assert_eq!(ok.as_series(), None);
+
+    Ok(())
+}
+
+#[test]
+fn should_collect_vector_label_keys() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" : {
+                "resultType" : "vector",
+                "result" : [
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "prometheus",
+                            "instance" : "localhost:9090"
+                        },
+                        "value": [ 1435781451.781, "1" ]
+                    },
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "node",
+                            "instance" : "localhost:9100",
+                            "zone" : "eu-west-1"
+                        },
+                        "value" : [ 1435781451.781, "0" ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    let ok = match res {
+        ApiResult::ApiOk(ok) => ok,
+        ApiResult::ApiErr(err) => panic!("unexpected error: {:?}", err),
+    };
+
+    assert_eq!(
+        vec!["instance", "job", "zone"],
+        ok.vector_label_keys(false).iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["__name__", "instance", "job", "zone"],
+        ok.vector_label_keys(true).iter().collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn should_deserialize_json_prom_matrix() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -359,6 +596,52 @@ fn should_deserialize_json_prom_matrix() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
+#[test]
+fn should_access_matrix_data() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" : {
+                "resultType" : "matrix",
+                "result" : [
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "prometheus",
+                            "instance" : "localhost:9090"
+                        },
+                        "values" : [
+                           [ 1435781430.781, "1" ],
+                           [ 1435781445.781, "1" ],
+                           [ 1435781460.781, "1" ]
+                        ]
+                    },
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "node",
+                            "instance" : "localhost:9091"
+                        },
+                        "values" : [
+                           [ 1435781430.781, "0" ],
+                           [ 1435781445.781, "0" ],
+                           [ 1435781460.781, "1" ]
+                        ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    let ranges = ok.as_range_matrix().unwrap();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(ranges[0].samples.len(), 3);
+    assert_eq!(ok.as_instant_vector(), None);
+
+    Ok(())
+}
+
 #[test]
 fn should_deserialize_json_prom_labels() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -424,6 +707,45 @@ fn should_deserialize_json_prom_labels() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
+#[test]
+fn should_access_labels_data() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" :[
+                "__name__",
+                "call",
+                "code",
+                "config",
+                "dialer_name",
+                "endpoint",
+                "event",
+                "goversion",
+                "handler",
+                "instance",
+                "interval",
+                "job",
+                "le",
+                "listener_name",
+                "name",
+                "quantile",
+                "reason",
+                "role",
+                "scrape_job",
+                "slice",
+                "version"
+            ]
+        }
+        "#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    let labels = ok.as_labels().unwrap();
+    assert_eq!(labels[0], "__name__");
+    assert_eq!(ok.as_series(), None);
+
+    Ok(())
+}
+
 #[test]
 fn should_deserialize_json_prom_label_values() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -510,6 +832,40 @@ fn should_deserialize_json_prom_series() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
+#[test]
+fn should_access_series_data() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" : [
+                {
+                    "__name__" : "up",
+                    "job" : "prometheus",
+                    "instance" : "localhost:9090"
+                },
+                {
+                    "__name__" : "up",
+                    "job" : "node",
+                    "instance" : "localhost:9091"
+                },
+                {
+                    "__name__" : "process_start_time_seconds",
+                    "job" : "prometheus",
+                    "instance" : "localhost:9090"
+                }
+            ]
+        }
+        "#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    let metrics = ok.as_series().unwrap();
+    assert_eq!(metrics.len(), 3);
+    assert_eq!(metrics[1].labels["job"], "node");
+    assert_eq!(ok.as_labels(), None);
+
+    Ok(())
+}
+
 #[test]
 fn should_deserialize_json_prom_targets() -> StdResult<(), std::io::Error> {
     let j = r#"
@@ -578,6 +934,7 @@ fn should_deserialize_json_prom_targets() -> StdResult<(), std::io::Error> {
                 active: vec![ActiveTarget {
                     discovered_labels: active_discovered_labels,
                     labels: active_labels,
+                    scrape_pool: None,
                     scrape_url: Url::parse("http://127.0.0.1:9090/metrics").unwrap(),
                     last_error: None,
                     last_scrape,
@@ -736,18 +1093,51 @@ fn should_deserialize_json_prom_config() -> StdResult<(), std::io::Error> {
     Ok(())
 }
 
-// FIXME: make this an actual tests
 #[test]
-fn should_serialize_rust_prom_config() -> StdResult<(), std::io::Error> {
-    let s = serde_json::to_string_pretty(&ApiResult::ApiOk(ApiOk {
-        data: Some(Data::Config(Config {
-            yaml: "CONTENT".to_owned(),
-        })),
-        warnings: Vec::new(),
-    }))?;
-
-    dbg!(s);
-
+fn should_hash_prom_config() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "yaml": "global:\n  scrape_interval: 15s\n",
+                "hash": "5e4e4c0cbbd7c2e9"
+            }
+        }
+        "#;
+
+    let config = serde_json::from_str::<ApiResult>(j)?.into_config().unwrap();
+    let same = Config {
+        yaml: "global:\n  scrape_interval: 15s\n".to_owned(),
+    };
+    let modified = Config {
+        yaml: "global:\n  scrape_interval: 30s\n".to_owned(),
+    };
+
+    assert_eq!(config.sha256(), same.sha256());
+    assert_ne!(config.sha256(), modified.sha256());
+    assert_eq!(
+        Config {
+            yaml: String::new()
+        }
+        .sha256(),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+
+    Ok(())
+}
+
+// FIXME: make this an actual tests
+#[test]
+fn should_serialize_rust_prom_config() -> StdResult<(), std::io::Error> {
+    let s = serde_json::to_string_pretty(&ApiResult::ApiOk(ApiOk {
+        data: Some(Data::Config(Config {
+            yaml: "CONTENT".to_owned(),
+        })),
+        warnings: Vec::new(),
+    }))?;
+
+    dbg!(s);
+
     Ok(())
 }
 
@@ -833,6 +1223,7 @@ fn should_deserialize_json_prom_rules() -> StdResult<(), std::io::Error> {
                                 labels: Some(data_groups_rules_alert_labels),
                                 state: AlertState::FIRING,
                                 value: String::from("1e+00"),
+                                keep_firing_since: None,
                             }]),
                             annotations: Some(data_groups_rules_annotations),
                             duration: Some(600),
@@ -867,3 +1258,1300 @@ fn should_deserialize_json_prom_rules() -> StdResult<(), std::io::Error> {
 
     Ok(())
 }
+
+#[test]
+fn should_diff_discovered_and_final_target_labels() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "discoveredLabels": {
+                "__address__": "127.0.0.1:9090",
+                "__metrics_path__": "/metrics",
+                "__scheme__": "http",
+                "job": "prometheus",
+                "env": "staging"
+            },
+            "labels": {
+                "instance": "127.0.0.1:9090",
+                "job": "prometheus",
+                "env": "production"
+            },
+            "scrapeUrl": "http://127.0.0.1:9090/metrics",
+            "lastError": "",
+            "lastScrape": "2017-01-17T15:07:44.723715405+01:00",
+            "health": "up"
+        }
+        "#;
+
+    let target = serde_json::from_str::<ActiveTarget>(j)?;
+    assert_eq!(
+        target.relabel_diff(),
+        vec![
+            LabelChange::Removed {
+                name: "__address__".to_owned(),
+                value: "127.0.0.1:9090".to_owned(),
+            },
+            LabelChange::Removed {
+                name: "__metrics_path__".to_owned(),
+                value: "/metrics".to_owned(),
+            },
+            LabelChange::Removed {
+                name: "__scheme__".to_owned(),
+                value: "http".to_owned(),
+            },
+            LabelChange::Changed {
+                name: "env".to_owned(),
+                from: "staging".to_owned(),
+                to: "production".to_owned(),
+            },
+            LabelChange::Added {
+                name: "instance".to_owned(),
+                value: "127.0.0.1:9090".to_owned(),
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_tsdb_stats() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "headStats": {
+                    "numSeries": 508,
+                    "chunkCount": 937,
+                    "minTime": 1591516800000,
+                    "maxTime": 1598896800143
+                },
+                "seriesCountByMetricName": [
+                    {
+                        "name": "net_conntrack_dialer_conn_failed_total",
+                        "value": 20
+                    }
+                ],
+                "labelValueCountByLabelName": [
+                    {
+                        "name": "__name__",
+                        "value": 211
+                    }
+                ],
+                "memoryInBytesByLabelName": [
+                    {
+                        "name": "__name__",
+                        "value": 8266
+                    }
+                ],
+                "seriesCountByLabelValuePair": [
+                    {
+                        "name": "job=prometheus",
+                        "value": 425
+                    }
+                ]
+            }
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    let stats = match res {
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::TsdbStats(stats)),
+            ..
+        }) => stats,
+        other => panic!("unexpected result: {:?}", other),
+    };
+
+    assert_eq!(stats.head_stats.num_series, 508);
+    assert_eq!(
+        stats.series_count_by_metric_name,
+        vec![TsdbStat {
+            name: "net_conntrack_dialer_conn_failed_total".to_owned(),
+            value: 20,
+        }]
+    );
+    assert_eq!(
+        stats.head_min_time(),
+        Some(Utc.with_ymd_and_hms(2020, 6, 7, 8, 0, 0).unwrap())
+    );
+    assert_eq!(
+        stats.head_max_time(),
+        Some(Utc.timestamp_millis_opt(1_598_896_800_143).unwrap())
+    );
+    assert_eq!(
+        stats.head_max_time().map(|t| t.to_rfc3339()),
+        Some("2020-08-31T18:00:00.143+00:00".to_owned())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_compute_alert_firing_duration() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "activeAt": "2018-07-04T20:27:12.60602144+02:00",
+            "annotations": {
+                "summary": "High request latency"
+            },
+            "labels": {
+                "alertname": "HighRequestLatency",
+                "severity": "page"
+            },
+            "state": "firing",
+            "value": "1e+00",
+            "keepFiringSince": "2018-07-04T20:37:12+02:00"
+        }
+        "#;
+
+    let alert = serde_json::from_str::<Alert>(j)?;
+    let now = Utc.with_ymd_and_hms(2018, 7, 4, 19, 27, 12).unwrap();
+
+    assert_eq!(
+        alert.keep_firing_since_time(),
+        Some(DateTime::parse_from_rfc3339("2018-07-04T18:37:12+00:00").unwrap())
+    );
+    assert_eq!(
+        alert.firing_duration(now),
+        Some(std::time::Duration::new(3599, 393_978_560))
+    );
+    assert_eq!(
+        alert.firing_duration(now - chrono::Duration::hours(2)),
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_compute_range_statistics() {
+    let samples = [15.0, 20.0, 35.0, 40.0, 50.0, f64::NAN]
+        .iter()
+        .enumerate()
+        .map(|(i, value)| Sample {
+            epoch: 1_435_781_430.0 + i as f64 * 15.0,
+            value: *value,
+        })
+        .collect();
+    let range = Range {
+        metric: Metric {
+            labels: HashMap::new(),
+        },
+        samples,
+    };
+
+    assert_eq!(range.percentile(50.0), Some(35.0));
+    assert_eq!(range.percentile(95.0), Some(48.0));
+    assert_eq!(range.percentile(0.0), Some(15.0));
+    assert_eq!(range.percentile(100.0), Some(50.0));
+    assert_eq!(range.percentile(101.0), None);
+    assert_eq!(range.mean(), Some(32.0));
+    assert_eq!(range.min(), Some(15.0));
+    assert_eq!(range.max(), Some(50.0));
+    assert_eq!(range.stddev(), Some(166_f64.sqrt()));
+
+    let empty = Range {
+        metric: Metric {
+            labels: HashMap::new(),
+        },
+        samples: vec![],
+    };
+    assert_eq!(empty.percentile(50.0), None);
+    assert_eq!(empty.mean(), None);
+    assert_eq!(empty.min(), None);
+    assert_eq!(empty.max(), None);
+    assert_eq!(empty.stddev(), None);
+}
+
+#[test]
+fn should_extract_metric_names_from_label_values() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" :[
+                "go_goroutines",
+                "prometheus_http_requests_total",
+                "up"
+            ]
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        res.into_labels_or_values().unwrap(),
+        vec![
+            "go_goroutines".to_owned(),
+            "prometheus_http_requests_total".to_owned(),
+            "up".to_owned(),
+        ]
+    );
+
+    let j = r#"
+        {
+            "status": "error",
+            "error": "Major",
+            "errorType": "Seriously Bad"
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    match res.into_labels_or_values() {
+        Err(ProqError::ApiError {
+            error_type,
+            error_message,
+        }) => {
+            assert_eq!(error_type, "Seriously Bad");
+            assert_eq!(error_message, "Major");
+        }
+        other => panic!("expected api error, got: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_interpret_scalar_as_bool() -> StdResult<(), std::io::Error> {
+    let scalar = |value: &str| {
+        format!(
+            r#"{{"status": "success", "data": {{"resultType": "scalar", "result": [1435781451.781, "{}"]}}}}"#,
+            value
+        )
+    };
+
+    let res = serde_json::from_str::<ApiResult>(&scalar("1"))?;
+    assert_eq!(res.as_bool(), Some(true));
+
+    let res = serde_json::from_str::<ApiResult>(&scalar("0"))?;
+    assert_eq!(res.as_bool(), Some(false));
+
+    let res = serde_json::from_str::<ApiResult>(&scalar("0.5"))?;
+    assert_eq!(res.as_bool(), None);
+
+    let res = serde_json::from_str::<ApiResult>(&scalar("NaN"))?;
+    assert_eq!(res.as_bool(), None);
+
+    Ok(())
+}
+
+#[test]
+fn should_group_exemplars_into_time_buckets() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": [
+                {
+                    "seriesLabels": {
+                        "__name__": "test_exemplar_metric_total",
+                        "instance": "localhost:8090",
+                        "job": "prometheus",
+                        "service": "bar"
+                    },
+                    "exemplars": [
+                        {
+                            "labels": {
+                                "trace_id": "EpTxMJ40fUus7aGY"
+                            },
+                            "value": "6",
+                            "timestamp": 1600096945.479
+                        }
+                    ]
+                },
+                {
+                    "seriesLabels": {
+                        "__name__": "test_exemplar_metric_total",
+                        "instance": "localhost:8090",
+                        "job": "prometheus",
+                        "service": "foo"
+                    },
+                    "exemplars": [
+                        {
+                            "labels": {
+                                "trace_id": "Olp9XHlq763ccsfa"
+                            },
+                            "value": "19",
+                            "timestamp": 1600096955.479
+                        },
+                        {
+                            "labels": {
+                                "trace_id": "hCtjygkIHwAN9vs4"
+                            },
+                            "value": "20",
+                            "timestamp": 1600096965.489
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    let ok = match res {
+        ApiResult::ApiOk(ok) => ok,
+        other => panic!("unexpected result: {:?}", other),
+    };
+
+    let trace = |id: &str, value: f64, timestamp: f64| {
+        let mut labels = HashMap::new();
+        labels.insert("trace_id".to_owned(), id.to_owned());
+        Exemplar {
+            labels,
+            value,
+            timestamp,
+        }
+    };
+
+    assert_eq!(
+        ok.exemplars_in_window(std::time::Duration::from_secs(20)),
+        vec![
+            (
+                1600096940_f64,
+                vec![
+                    trace("EpTxMJ40fUus7aGY", 6_f64, 1600096945.479),
+                    trace("Olp9XHlq763ccsfa", 19_f64, 1600096955.479),
+                ]
+            ),
+            (
+                1600096960_f64,
+                vec![trace("hCtjygkIHwAN9vs4", 20_f64, 1600096965.489)]
+            ),
+        ]
+    );
+    assert!(ok
+        .exemplars_in_window(std::time::Duration::from_secs(0))
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn should_index_series_and_label_values() -> StdResult<(), std::io::Error> {
+    let j = r#"
+        [
+            {
+                "__name__" : "up",
+                "job" : "prometheus",
+                "instance" : "localhost:9090"
+            },
+            {
+                "__name__" : "process_start_time_seconds",
+                "job" : "prometheus",
+                "instance" : "localhost:9090"
+            }
+        ]
+        "#;
+
+    let series = serde_json::from_str::<Series>(j)?;
+    assert_eq!(series[0].labels["__name__"], "up");
+    assert_eq!(series[1].labels["__name__"], "process_start_time_seconds");
+
+    let values = LabelsOrValues(vec!["node".to_owned(), "prometheus".to_owned()]);
+    assert_eq!(values[1], "prometheus");
+
+    Ok(())
+}
+
+#[test]
+fn should_extract_distinct_scrape_pools_from_targets() -> StdResult<(), std::io::Error> {
+    let target = |pool: Option<&str>, job: &str, instance: &str| {
+        format!(
+            r#"{{
+                "discoveredLabels": {{}},
+                "labels": {{ "instance": "{instance}", "job": "{job}" }},
+                {pool}
+                "scrapeUrl": "http://{instance}/metrics",
+                "lastError": "",
+                "lastScrape": "2017-01-17T15:07:44.723715405+01:00",
+                "health": "up"
+            }}"#,
+            instance = instance,
+            job = job,
+            pool = pool
+                .map(|p| format!(r#""scrapePool": "{}","#, p))
+                .unwrap_or_default()
+        )
+    };
+    let j = format!(
+        r#"{{
+            "status": "success",
+            "data": {{
+                "activeTargets": [{}, {}, {}, {}],
+                "droppedTargets": []
+            }}
+        }}"#,
+        target(Some("prometheus"), "prometheus", "127.0.0.1:9090"),
+        target(Some("node"), "node", "127.0.0.1:9100"),
+        target(Some("node"), "node", "127.0.0.2:9100"),
+        target(None, "blackbox", "127.0.0.1:9115"),
+    );
+
+    let targets = serde_json::from_str::<ApiResult>(&j)?
+        .into_targets()
+        .unwrap();
+    assert_eq!(targets.active[0].scrape_pool, Some("prometheus".to_owned()));
+    assert_eq!(
+        targets.scrape_pools(),
+        vec![
+            "blackbox".to_owned(),
+            "node".to_owned(),
+            "prometheus".to_owned(),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_extract_empty_label_values() -> StdResult<(), std::io::Error> {
+    let j = r#"{"status": "success", "data": []}"#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        res.clone().into_labels_or_values().unwrap(),
+        Vec::<String>::new()
+    );
+    assert_eq!(res.into_series().unwrap(), Vec::<Metric>::new());
+
+    Ok(())
+}
+
+#[test]
+fn should_access_empty_label_values_data() -> StdResult<(), std::io::Error> {
+    let j = r#"{"status": "success", "data": []}"#;
+
+    let ok = serde_json::from_str::<ApiResult>(j)?.into_result().unwrap();
+    assert_eq!(ok.as_labels(), Some(&[][..]));
+    assert_eq!(ok.as_series(), Some(&[][..]));
+
+    Ok(())
+}
+
+fn build_info_response(build_info: &str) -> String {
+    format!(r#"{{"status":"success","data":{}}}"#, build_info)
+}
+
+#[test]
+fn should_classify_backend_from_build_info() -> StdResult<(), serde_json::Error> {
+    let samples = vec![
+        (
+            r#"{"version":"2.45.0","revision":"8ef767e396bf8445f009f945b0162fd71827f445","branch":"HEAD","buildUser":"root@920118f645b7","buildDate":"20230623-15:09:49","goVersion":"go1.20.5"}"#,
+            BackendKind::Prometheus,
+        ),
+        (
+            r#"{"version":"0.32.2","revision":"3d98d7ce7a254b893e4c8ee8122f7f6edd3174bd","branch":"HEAD","buildUser":"root@thanos-builder","buildDate":"20230913-14:23:05","goVersion":"go1.20.8"}"#,
+            BackendKind::Thanos,
+        ),
+        (
+            r#"{"application":"Grafana Mimir","version":"2.10.0","revision":"4ad5b54","branch":"release-2.10","goVersion":"go1.21.1","features":{}}"#,
+            BackendKind::Cortex,
+        ),
+        (
+            r#"{"version":"1.15.1","revision":"0f35b22","branch":"release-1.15","buildUser":"cortex@builder","buildDate":"20230601-10:00:00","goVersion":"go1.20.4"}"#,
+            BackendKind::Cortex,
+        ),
+        (
+            r#"{"version":"victoria-metrics-20231002-235823-tags-v1.94.0-0-g9d42b5bdd","revision":"9d42b5bdd","branch":"HEAD","buildUser":"","buildDate":"","goVersion":"go1.21.1"}"#,
+            BackendKind::VictoriaMetrics,
+        ),
+    ];
+
+    for (build_info, expected) in samples {
+        let res: ApiResult = serde_json::from_str(&build_info_response(build_info))?;
+        assert_eq!(res.into_build_info().unwrap().backend_kind(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_categorize_warnings() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "resultType": "vector",
+                "result": []
+            },
+            "warnings": [
+                "results truncated due to limit",
+                "1500 results were dropped due to the series limit",
+                "PromQL info: this feature is experimental and may change",
+                "partial response: store gateway 10.0.0.1:10901 unavailable",
+                "remote read error: context deadline exceeded",
+                "PromQL warning: encountered a mix of histograms and floats"
+            ]
+        }
+        "#;
+
+    let res: ApiResult = serde_json::from_str(j)?;
+    let ok = match res {
+        ApiResult::ApiOk(ok) => ok,
+        ApiResult::ApiErr(err) => panic!("expected success, got: {:?}", err),
+    };
+
+    assert_eq!(
+        ok.parsed_warnings(),
+        vec![
+            Warning::Truncated,
+            Warning::Truncated,
+            Warning::Experimental,
+            Warning::PartialData,
+            Warning::PartialData,
+            Warning::Other("PromQL warning: encountered a mix of histograms and floats".to_owned()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_detect_step_of_range_result() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status" : "success",
+            "data" : {
+                "resultType" : "matrix",
+                "result" : [
+                    {
+                        "metric" : {
+                            "__name__" : "up",
+                            "job" : "prometheus",
+                            "instance" : "localhost:9090"
+                        },
+                        "values" : [
+                           [ 1435781430.781, "1" ],
+                           [ 1435781445.781, "1" ],
+                           [ 1435781475.781, "1" ]
+                        ]
+                    }
+                ]
+            }
+        }
+        "#;
+
+    let res: ApiResult = serde_json::from_str(j)?;
+    match res {
+        ApiResult::ApiOk(ok) => assert_eq!(ok.detected_step(), Some(Duration::from_secs(15))),
+        ApiResult::ApiErr(err) => panic!("expected success, got: {:?}", err),
+    }
+
+    let instant = ApiOk {
+        data: Some(Data::Expression(Expression::Instant(Vec::new()))),
+        warnings: Vec::new(),
+    };
+    assert_eq!(instant.detected_step(), None);
+
+    Ok(())
+}
+
+#[test]
+fn should_list_rule_group_names_and_files() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "data": {
+                "groups": [
+                    {
+                        "rules": [
+                            {
+                                "health": "ok",
+                                "name": "job:http_inprogress_requests:sum",
+                                "query": "sum(http_inprogress_requests) by (job)",
+                                "type": "recording"
+                            }
+                        ],
+                        "file": "/etc/prometheus/rules/recording.yaml",
+                        "interval": 60,
+                        "name": "http"
+                    },
+                    {
+                        "rules": [],
+                        "file": "/etc/prometheus/rules/alerting.yaml",
+                        "interval": 30,
+                        "name": "availability"
+                    },
+                    {
+                        "rules": [],
+                        "file": "/etc/prometheus/rules/recording.yaml",
+                        "interval": 60,
+                        "name": "node"
+                    }
+                ]
+            },
+            "status": "success"
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    let rules = match res {
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::Rules(rules)),
+            ..
+        }) => rules,
+        res => panic!("expected rules, got: {:?}", res),
+    };
+
+    assert_eq!(rules.group_names(), vec!["availability", "http", "node"]);
+    assert_eq!(
+        rules.files(),
+        vec![
+            "/etc/prometheus/rules/alerting.yaml",
+            "/etc/prometheus/rules/recording.yaml"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_flatten_rules_across_groups_and_files() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "data": {
+                "groups": [
+                    {
+                        "rules": [
+                            {
+                                "health": "ok",
+                                "name": "job:http_inprogress_requests:sum",
+                                "query": "sum(http_inprogress_requests) by (job)",
+                                "type": "recording"
+                            }
+                        ],
+                        "file": "/etc/prometheus/rules/recording.yaml",
+                        "interval": 60,
+                        "name": "http"
+                    },
+                    {
+                        "rules": [
+                            {
+                                "alerts": [],
+                                "annotations": {
+                                    "summary": "Instance down"
+                                },
+                                "duration": 300,
+                                "health": "ok",
+                                "labels": {
+                                    "severity": "page"
+                                },
+                                "name": "InstanceDown",
+                                "query": "up == 0",
+                                "type": "alerting"
+                            }
+                        ],
+                        "file": "/etc/prometheus/rules/alerting.yaml",
+                        "interval": 30,
+                        "name": "availability"
+                    },
+                    {
+                        "rules": [
+                            {
+                                "health": "ok",
+                                "name": "instance:node_cpu:rate5m",
+                                "query": "rate(node_cpu_seconds_total[5m])",
+                                "type": "recording"
+                            },
+                            {
+                                "health": "ok",
+                                "name": "instance:node_memory_utilisation:ratio",
+                                "query": "1 - node_memory_MemAvailable_bytes / node_memory_MemTotal_bytes",
+                                "type": "recording"
+                            }
+                        ],
+                        "file": "/etc/prometheus/rules/recording.yaml",
+                        "interval": 60,
+                        "name": "node"
+                    }
+                ]
+            },
+            "status": "success"
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    let rules = match res {
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::Rules(rules)),
+            ..
+        }) => rules,
+        res => panic!("expected rules, got: {:?}", res),
+    };
+
+    let names = |rules: Vec<&Rule>| {
+        rules
+            .into_iter()
+            .map(|r| r.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(rules.all_rules()),
+        vec![
+            "job:http_inprogress_requests:sum",
+            "InstanceDown",
+            "instance:node_cpu:rate5m",
+            "instance:node_memory_utilisation:ratio"
+        ]
+    );
+    assert_eq!(
+        names(rules.rules_in_file("/etc/prometheus/rules/recording.yaml")),
+        vec![
+            "job:http_inprogress_requests:sum",
+            "instance:node_cpu:rate5m",
+            "instance:node_memory_utilisation:ratio"
+        ]
+    );
+    assert_eq!(
+        names(rules.rules_in_file("/etc/prometheus/rules/alerting.yaml")),
+        vec!["InstanceDown"]
+    );
+    assert!(rules
+        .rules_in_file("/etc/prometheus/rules/missing.yaml")
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_metadata() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "cortex_ring_tokens": [
+                    {
+                        "type": "gauge",
+                        "help": "Number of tokens in the ring",
+                        "unit": ""
+                    }
+                ],
+                "http_requests_total": [
+                    {
+                        "type": "counter",
+                        "help": "Number of HTTP requests",
+                        "unit": ""
+                    },
+                    {
+                        "type": "counter",
+                        "help": "Amount of HTTP requests",
+                        "unit": ""
+                    }
+                ]
+            }
+        }
+        "#;
+
+    let metadata_entry = |metric_type: &str, help: &str| MetricMetadata {
+        metric_type: metric_type.to_owned(),
+        help: help.to_owned(),
+        unit: String::new(),
+    };
+    let mut metadata: HashMap<String, Vec<MetricMetadata>> = HashMap::new();
+    metadata.insert(
+        "cortex_ring_tokens".to_owned(),
+        vec![metadata_entry("gauge", "Number of tokens in the ring")],
+    );
+    metadata.insert(
+        "http_requests_total".to_owned(),
+        vec![
+            metadata_entry("counter", "Number of HTTP requests"),
+            metadata_entry("counter", "Amount of HTTP requests"),
+        ],
+    );
+    let expected = ApiResult::ApiOk(ApiOk {
+        data: Some(Data::Metadata(metadata)),
+        warnings: Vec::new(),
+    });
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(expected, res);
+
+    let s = serde_json::to_string(&res)?;
+    assert_eq!(expected, serde_json::from_str::<ApiResult>(&s)?);
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_target_metadata() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": [
+                {
+                    "target": {
+                        "instance": "127.0.0.1:9090",
+                        "job": "prometheus"
+                    },
+                    "metric": "prometheus_treecache_zookeeper_failures_total",
+                    "type": "counter",
+                    "help": "The total number of ZooKeeper failures.",
+                    "unit": ""
+                },
+                {
+                    "target": {
+                        "instance": "127.0.0.1:9090",
+                        "job": "prometheus"
+                    },
+                    "metric": "prometheus_tsdb_reloads_total",
+                    "type": "counter",
+                    "help": "Number of times the database reloaded block data from disk.",
+                    "unit": ""
+                },
+                {
+                    "target": {
+                        "instance": "localhost:9100",
+                        "job": "node"
+                    },
+                    "metric": "node_memory_MemAvailable_bytes",
+                    "type": "gauge",
+                    "help": "Memory information field MemAvailable_bytes.",
+                    "unit": "bytes"
+                }
+            ]
+        }
+        "#;
+
+    let target = |instance: &str, job: &str| {
+        let mut target: HashMap<String, String> = HashMap::new();
+        target.insert("instance".to_owned(), instance.to_owned());
+        target.insert("job".to_owned(), job.to_owned());
+        target
+    };
+    let expected = vec![
+        TargetMetadataEntry {
+            target: target("127.0.0.1:9090", "prometheus"),
+            metric: Some("prometheus_treecache_zookeeper_failures_total".to_owned()),
+            metric_type: "counter".to_owned(),
+            help: "The total number of ZooKeeper failures.".to_owned(),
+            unit: String::new(),
+        },
+        TargetMetadataEntry {
+            target: target("127.0.0.1:9090", "prometheus"),
+            metric: Some("prometheus_tsdb_reloads_total".to_owned()),
+            metric_type: "counter".to_owned(),
+            help: "Number of times the database reloaded block data from disk.".to_owned(),
+            unit: String::new(),
+        },
+        TargetMetadataEntry {
+            target: target("localhost:9100", "node"),
+            metric: Some("node_memory_MemAvailable_bytes".to_owned()),
+            metric_type: "gauge".to_owned(),
+            help: "Memory information field MemAvailable_bytes.".to_owned(),
+            unit: "bytes".to_owned(),
+        },
+    ];
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    let s = serde_json::to_string(&res)?;
+    assert_eq!(res.into_target_metadata().unwrap(), expected);
+    assert_eq!(
+        serde_json::from_str::<ApiResult>(&s)?
+            .into_target_metadata()
+            .unwrap(),
+        expected
+    );
+
+    let empty = r#"{"status": "success", "data": []}"#;
+    assert!(serde_json::from_str::<ApiResult>(empty)?
+        .into_target_metadata()
+        .unwrap()
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn should_return_output_metric_of_recording_rules_only() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        [
+            {
+                "health": "ok",
+                "name": "job:http_inprogress_requests:sum",
+                "query": "sum(http_inprogress_requests) by (job)",
+                "type": "recording"
+            },
+            {
+                "duration": 600,
+                "health": "ok",
+                "name": "HighRequestLatency",
+                "query": "job:request_latency_seconds:mean5m{job=\"myjob\"} > 0.5",
+                "type": "alerting"
+            }
+        ]
+        "#;
+
+    let rules: Vec<Rule> = serde_json::from_str(j)?;
+    assert_eq!(
+        rules[0].output_metric(),
+        Some("job:http_inprogress_requests:sum")
+    );
+    assert_eq!(rules[1].output_metric(), None);
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_build_info() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "version": "2.13.1",
+                "revision": "cb7cbad5f9a2823a622aaa668833ca04f50a0ea7",
+                "branch": "master",
+                "buildUser": "julius@desktop",
+                "buildDate": "20191102-16:19:59",
+                "goVersion": "go1.13.1"
+            }
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::BuildInfo(BuildInfo {
+                application: None,
+                version: "2.13.1".to_owned(),
+                revision: "cb7cbad5f9a2823a622aaa668833ca04f50a0ea7".to_owned(),
+                branch: "master".to_owned(),
+                build_user: "julius@desktop".to_owned(),
+                build_date: "20191102-16:19:59".to_owned(),
+                go_version: "go1.13.1".to_owned(),
+            })),
+            warnings: Vec::new(),
+        }),
+        res
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_wal_replay() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "min": 2,
+                "max": 5,
+                "current": 3,
+                "state": "in progress"
+            }
+        }
+        "#;
+
+    let wal_replay = WalReplay {
+        min: 2,
+        max: 5,
+        current: 3,
+        state: Some(WalReplayState::INPROGRESS),
+    };
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::WalReplay(wal_replay.clone())),
+            warnings: Vec::new(),
+        }),
+        res
+    );
+    assert_eq!(wal_replay.progress_percent(), 50.0);
+
+    let done = WalReplay {
+        current: 5,
+        state: Some(WalReplayState::DONE),
+        ..wal_replay
+    };
+    assert_eq!(done.progress_percent(), 100.0);
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_wal_replay_without_state() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "min": 2,
+                "max": 5,
+                "current": 3
+            }
+        }
+        "#;
+
+    let wal_replay = WalReplay {
+        min: 2,
+        max: 5,
+        current: 3,
+        state: None,
+    };
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::WalReplay(wal_replay.clone())),
+            warnings: Vec::new(),
+        }),
+        res
+    );
+    assert_eq!(wal_replay.progress_percent(), 50.0);
+
+    let done = WalReplay {
+        current: 5,
+        ..wal_replay
+    };
+    assert_eq!(done.progress_percent(), 100.0);
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_runtime_info() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": {
+                "startTime": "2019-11-02T17:23:59.301361365+01:00",
+                "CWD": "/",
+                "reloadConfigSuccess": true,
+                "lastConfigTime": "2019-11-02T17:23:59+01:00",
+                "chunkCount": 873,
+                "timeSeriesCount": 873,
+                "corruptionCount": 0,
+                "goroutineCount": 48,
+                "GOMAXPROCS": 4,
+                "GOGC": "",
+                "GODEBUG": "",
+                "storageRetention": "15d"
+            }
+        }
+        "#;
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        ApiResult::ApiOk(ApiOk {
+            data: Some(Data::RuntimeInfo(RuntimeInfo {
+                start_time: DateTime::parse_from_rfc3339("2019-11-02T17:23:59.301361365+01:00")
+                    .unwrap(),
+                cwd: "/".to_owned(),
+                reload_config_success: true,
+                last_config_time: DateTime::parse_from_rfc3339("2019-11-02T17:23:59+01:00")
+                    .unwrap(),
+                chunk_count: 873,
+                time_series_count: 873,
+                corruption_count: 0,
+                goroutine_count: 48,
+                gomaxprocs: 4,
+                storage_retention: "15d".to_owned(),
+            })),
+            warnings: Vec::new(),
+        }),
+        res
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_extract_empty_array_data_for_each_endpoint() -> StdResult<(), serde_json::Error> {
+    let j = r#"{"status": "success", "data": []}"#;
+    let res = serde_json::from_str::<ApiResult>(j)?;
+
+    assert_eq!(
+        res.clone().into_labels_or_values().unwrap(),
+        Vec::<String>::new()
+    );
+    assert_eq!(res.clone().into_series().unwrap(), Vec::<Metric>::new());
+    assert!(res.clone().into_exemplars().unwrap().is_empty());
+    assert!(res.into_instants().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn should_borrow_result_data() -> StdResult<(), serde_json::Error> {
+    let vector = serde_json::from_str::<ApiResult>(
+        r#"{"status":"success","data":{"resultType":"vector","result":[
+            {"metric":{"__name__":"up","job":"prometheus"},"value":[1435781451.781,"1"]}
+        ]}}"#,
+    )?;
+    assert!(vector.as_err().is_none());
+    assert_eq!(vector.as_ok().unwrap().warnings, Vec::<String>::new());
+    let instants = vector.as_instants().unwrap();
+    assert_eq!(instants.len(), 1);
+    assert_eq!(instants[0].metric.name(), Some("up"));
+    assert_eq!(instants, vector.clone().into_instants().unwrap().as_slice());
+    match vector.as_series() {
+        Err(ProqError::UnexpectedData(_)) => {}
+        other => panic!("expected unexpected data error, got: {:?}", other),
+    }
+
+    let labels = serde_json::from_str::<ApiResult>(r#"{"status":"success","data":["job"]}"#)?;
+    assert_eq!(labels.as_labels_or_values().unwrap(), ["job".to_owned()]);
+    assert!(labels.as_series().is_err());
+
+    let empty = serde_json::from_str::<ApiResult>(r#"{"status":"success","data":[]}"#)?;
+    assert!(empty.as_labels_or_values().unwrap().is_empty());
+    assert!(empty.as_series().unwrap().is_empty());
+    assert!(empty.as_exemplars().unwrap().is_empty());
+    assert!(empty.as_target_metadata().unwrap().is_empty());
+    assert!(empty.as_targets().is_err());
+
+    let error = serde_json::from_str::<ApiResult>(
+        r#"{"status":"error","errorType":"bad_data","error":"parse error"}"#,
+    )?;
+    assert!(error.as_ok().is_none());
+    assert_eq!(error.as_err().unwrap().error_type, "bad_data");
+    match error.as_instants() {
+        Err(ProqError::ApiError {
+            error_type,
+            error_message,
+        }) => {
+            assert_eq!(error_type, "bad_data");
+            assert_eq!(error_message, "parse error");
+        }
+        other => panic!("expected api error, got: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_not_confuse_non_empty_array_data() -> StdResult<(), serde_json::Error> {
+    let labels = serde_json::from_str::<ApiResult>(r#"{"status": "success", "data": ["job"]}"#)?;
+    assert!(labels.clone().into_series().is_err());
+    assert!(labels.clone().into_exemplars().is_err());
+    assert_eq!(
+        labels.into_labels_or_values().unwrap(),
+        vec!["job".to_owned()]
+    );
+
+    let series = serde_json::from_str::<ApiResult>(
+        r#"{"status": "success", "data": [{"__name__": "up", "job": "prometheus"}]}"#,
+    )?;
+    assert!(series.clone().into_labels_or_values().is_err());
+    assert!(series.clone().into_exemplars().is_err());
+    assert_eq!(series.into_series().unwrap().len(), 1);
+
+    let exemplars = serde_json::from_str::<ApiResult>(
+        r#"{"status": "success", "data": [{"seriesLabels": {"__name__": "up"}, "exemplars": []}]}"#,
+    )?;
+    assert!(exemplars.clone().into_labels_or_values().is_err());
+    assert!(exemplars.clone().into_series().is_err());
+    assert_eq!(exemplars.into_exemplars().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn should_deserialize_json_prom_exemplars() -> StdResult<(), serde_json::Error> {
+    let j = r#"
+        {
+            "status": "success",
+            "data": [
+                {
+                    "seriesLabels": {
+                        "__name__": "test_exemplar_metric_total",
+                        "instance": "localhost:8090",
+                        "job": "prometheus",
+                        "service": "bar"
+                    },
+                    "exemplars": [
+                        {
+                            "labels": {
+                                "traceID": "EpTxMJ40fUus7aGY"
+                            },
+                            "value": "6",
+                            "timestamp": 1600096945.479
+                        }
+                    ]
+                }
+            ]
+        }
+        "#;
+
+    let mut series_labels: HashMap<String, String> = HashMap::new();
+    series_labels.insert(
+        "__name__".to_owned(),
+        "test_exemplar_metric_total".to_owned(),
+    );
+    series_labels.insert("instance".to_owned(), "localhost:8090".to_owned());
+    series_labels.insert("job".to_owned(), "prometheus".to_owned());
+    series_labels.insert("service".to_owned(), "bar".to_owned());
+
+    let mut labels: HashMap<String, String> = HashMap::new();
+    labels.insert("traceID".to_owned(), "EpTxMJ40fUus7aGY".to_owned());
+
+    let res = serde_json::from_str::<ApiResult>(j)?;
+    assert_eq!(
+        res.into_exemplars().unwrap(),
+        vec![ExemplarSet {
+            series_labels,
+            exemplars: vec![Exemplar {
+                labels,
+                value: 6_f64,
+                timestamp: 1600096945.479,
+            }],
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn should_compute_label_facets_of_series() {
+    let metric = |pairs: &[(&str, &str)]| Metric {
+        labels: pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect(),
+    };
+    let series = Series(vec![
+        metric(&[
+            ("__name__", "up"),
+            ("job", "prometheus"),
+            ("instance", "b:9090"),
+        ]),
+        metric(&[("__name__", "up"), ("job", "node"), ("instance", "a:9100")]),
+        metric(&[("__name__", "up"), ("job", "node"), ("instance", "b:9100")]),
+        metric(&[("__name__", "node_load1"), ("job", "node"), ("cpu", "0")]),
+    ]);
+
+    let facets = series.facets();
+    assert_eq!(facets.len(), 4);
+    assert_eq!(facets["__name__"], vec!["node_load1", "up"]);
+    assert_eq!(facets["job"], vec!["node", "prometheus"]);
+    assert_eq!(facets["instance"], vec!["a:9100", "b:9090", "b:9100"]);
+    assert_eq!(facets["cpu"], vec!["0"]);
+
+    assert!(Series(Vec::new()).facets().is_empty());
+}
+
+#[test]
+fn should_convert_api_result_into_result() {
+    let ok = ApiOk {
+        data: Some(Data::LabelsOrValues(LabelsOrValues(vec!["job".to_owned()]))),
+        warnings: vec!["partial".to_owned()],
+    };
+    assert_eq!(ApiResult::ApiOk(ok.clone()).into_result(), Ok(ok));
+
+    let err = ApiErr {
+        error_type: "timeout".to_owned(),
+        error_message: "query timed out".to_owned(),
+        data: Some(Data::Series(Series(Vec::new()))),
+        warnings: Vec::new(),
+    };
+    assert_eq!(
+        ApiResult::ApiErr(err.clone()).into_result(),
+        Err(err.clone())
+    );
+
+    let propagate = || -> Result<ApiOk, ProqError> { Ok(ApiResult::ApiErr(err).into_result()?) };
+    match propagate() {
+        Err(ProqError::ApiError {
+            error_type,
+            error_message,
+        }) => {
+            assert_eq!(error_type, "timeout");
+            assert_eq!(error_message, "query timed out");
+        }
+        res => panic!("expected api error, got: {:?}", res),
+    }
+}
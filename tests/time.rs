@@ -0,0 +1,60 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use proq::errors::ProqError;
+use proq::time::parse_relative_time;
+
+#[test]
+fn should_parse_relative_time_expressions() {
+    let now = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+
+    assert_eq!(parse_relative_time("now", now).unwrap(), now);
+    assert_eq!(parse_relative_time(" now ", now).unwrap(), now);
+    assert_eq!(
+        parse_relative_time("now-1h", now).unwrap(),
+        now - Duration::hours(1)
+    );
+    assert_eq!(
+        parse_relative_time("now-30m", now).unwrap(),
+        now - Duration::minutes(30)
+    );
+    assert_eq!(
+        parse_relative_time("now-7d", now).unwrap(),
+        now - Duration::days(7)
+    );
+    assert_eq!(
+        parse_relative_time("now - 2w", now).unwrap(),
+        now - Duration::weeks(2)
+    );
+    assert_eq!(
+        parse_relative_time("now+15s", now).unwrap(),
+        now + Duration::seconds(15)
+    );
+    assert_eq!(
+        parse_relative_time("now-1y", now).unwrap(),
+        now - Duration::days(365)
+    );
+}
+
+#[test]
+fn should_reject_invalid_relative_time_expressions() {
+    let now = Utc.timestamp_opt(1_435_781_451, 0).unwrap();
+
+    for expr in &[
+        "",
+        "today",
+        "now-",
+        "now-h",
+        "now-1",
+        "now-1x",
+        "now-1.5h",
+        "now*2h",
+        "1h",
+        "now-1h-1m",
+        "now-99999999999d",
+    ] {
+        match parse_relative_time(expr, now) {
+            Err(ProqError::InvalidParameter(_)) => {}
+            res => panic!("expected invalid parameter for {:?}, got: {:?}", expr, res),
+        }
+    }
+}